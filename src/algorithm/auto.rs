@@ -0,0 +1,127 @@
+//! Tries several configurations on a graph and keeps the best layout.
+use std::thread;
+
+use log::{debug, info};
+use petgraph::stable_graph::StableDiGraph;
+
+use super::{start, Control, Edge, Vertex};
+use crate::configure::{Config, CrossingMinimization, QualityWeights, RankingType};
+use crate::{Cancelled, Drawings};
+
+/// Lays out the graph with each configuration of [candidates] and returns the
+/// layout with the lowest score, together with its configuration. Ties are
+/// won by the earlier configuration, i.e. the given one.
+///
+/// All configurations share `control`, so cancelling it stops all of them.
+pub(crate) fn start_auto(
+    graph: StableDiGraph<Vertex, Edge>,
+    config: Config,
+    weights: QualityWeights,
+    control: &Control,
+) -> Result<(Drawings<usize>, Config), Cancelled> {
+    let candidates = candidates(config);
+    let threads = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(candidates.len());
+    info!(target: "layouting",
+        "Trying {} configurations on {threads} threads",
+        candidates.len()
+    );
+    let run = |config: &Config| -> Result<_, Cancelled> {
+        let drawings = start(graph.clone(), *config, control)?;
+        let score = drawings
+            .iter()
+            .map(|d| weights.score(&d.metrics, config.vertex_spacing))
+            .sum::<f64>();
+        debug!(target: "layouting",
+            "{:?}, {:?}, transpose: {}: score {score}",
+            config.ranking_type,
+            config.c_minimization,
+            config.transpose
+        );
+        Ok((drawings, *config, score))
+    };
+    let results = if threads > 1 {
+        let chunk_size = candidates.len().div_ceil(threads);
+        thread::scope(|s| {
+            candidates
+                .chunks(chunk_size)
+                .map(|chunk| s.spawn(|| chunk.iter().map(run).collect::<Vec<_>>()))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect::<Result<Vec<_>, _>>()
+        })
+    } else {
+        candidates.iter().map(run).collect()
+    }?;
+    let (drawings, config, _) = results
+        .into_iter()
+        .min_by(|a, b| a.2.total_cmp(&b.2))
+        .unwrap();
+    Ok((drawings, config))
+}
+
+/// Returns the given configuration, followed by all combinations of ranking
+/// types, crossing minimization heuristics and transpose which differ from
+/// it.
+fn candidates(config: Config) -> Vec<Config> {
+    let mut candidates = vec![config];
+    for ranking_type in [
+        RankingType::MinimizeEdgeLength,
+        RankingType::Up,
+        RankingType::Down,
+        RankingType::NodePromotion,
+    ] {
+        for c_minimization in [
+            CrossingMinimization::Barycenter,
+            CrossingMinimization::Median,
+            CrossingMinimization::Sifting,
+        ] {
+            for transpose in [true, false] {
+                let candidate = Config {
+                    ranking_type,
+                    c_minimization,
+                    transpose,
+                    ..config
+                };
+                if (ranking_type, c_minimization, transpose)
+                    != (config.ranking_type, config.c_minimization, config.transpose)
+                {
+                    candidates.push(candidate);
+                }
+            }
+        }
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::candidates;
+    use crate::configure::{Config, CrossingMinimization, RankingType};
+
+    #[test]
+    fn candidates_start_with_config() {
+        let config = Config {
+            ranking_type: RankingType::CoffmanGraham { max_width: 2 },
+            ..Default::default()
+        };
+        let configs = candidates(config);
+        assert_eq!(configs.len(), 25);
+        assert_eq!(configs[0].ranking_type, config.ranking_type);
+
+        // the default configuration is one of the combinations
+        let configs = candidates(Config::default());
+        assert_eq!(configs.len(), 24);
+        assert_eq!(
+            configs
+                .iter()
+                .filter(|c| c.ranking_type == RankingType::MinimizeEdgeLength
+                    && c.c_minimization == CrossingMinimization::Barycenter
+                    && c.transpose)
+                .count(),
+            1
+        );
+    }
+}
@@ -1,14 +1,16 @@
 //! The implementation roughly follows sugiyamas algorithm for creating
 //! a layered graph layout.
 //!
-//! Usually Sugiyamas algorithm consists of 4 Phases:
-//! 1. Remove Cycles
-//! 2. Assign each vertex to a rank/layer
-//! 3. Reorder vertices in each rank to reduce crossings
-//! 4. Calculate the final coordinates.
+//! Usually Sugiyamas algorithm consists of 4 Phases, which are followed by
+//! routing the edges:
+//! 0. Remove cycles, by reversing edges (see [CycleRemoval])
+//! 1. Assign each vertex to a rank/layer (see [RankingType])
+//! 2. Reorder vertices in each rank to reduce crossings (see [CrossingMinimization])
+//! 3. Calculate the final coordinates (see [CoordinateAssignment])
+//! 4. Route the edges through the dummy vertices and clip them to the shapes
+//!    of the vertices (see [EdgeRouting])
 //!
-//! Currently, phase 2 to 4 are implemented, Cycle removal might be added at
-//! a later time.
+//! The phases are numbered like the submodules which implement them.
 //!
 //! The whole algorithm roughly follows the 1993 paper "A technique for drawing
 //! directed graphs" by Gansner et al. It can be found
@@ -16,24 +18,31 @@
 //!
 //! See the submodules for each phase for more details on the implementation
 //! and references used.
-use std::collections::HashMap;
+use std::sync::Arc;
 
 use log::{debug, info};
 use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableDiGraph};
 
-use crate::configure::{Config, CrossingMinimization, RankingType};
-use crate::{util::weakly_connected_components, Layout, Layouts};
+use crate::configure::{
+    Config, CoordinateAssignment, CrossingMinimization, CycleRemoval, EdgeRouting, RankingType,
+};
+use crate::{
+    util::weakly_connected_components, CancellationToken, Cancelled, Drawing, Drawings, Layouts,
+    Metrics, Phase, Progress, VertexShape,
+};
+pub(super) use auto::start_auto;
 use p0_cycle_removal as p0;
 use p1_layering as p1;
 use p2_reduce_crossings as p2;
 use p3_calculate_coordinates as p3;
+use p4_route_edges as p4;
 
-use self::p3_calculate_coordinates::VDir;
-
+mod auto;
 mod p0_cycle_removal;
 mod p1_layering;
 mod p2_reduce_crossings;
 mod p3_calculate_coordinates;
+mod p4_route_edges;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub(super) struct Vertex {
@@ -49,6 +58,7 @@ pub(super) struct Vertex {
     align: NodeIndex,
     shift: isize,
     sink: NodeIndex,
+    shape: VertexShape,
 }
 
 impl Vertex {
@@ -59,6 +69,10 @@ impl Vertex {
         }
     }
 
+    pub(super) fn set_shape(&mut self, shape: VertexShape) {
+        self.shape = shape;
+    }
+
     #[cfg(test)]
     fn new_test_p1(low: u32, lim: u32, parent: Option<NodeIndex>, is_tree_vertex: bool) -> Self {
         Self {
@@ -74,6 +88,7 @@ impl Vertex {
             align: 0.into(),
             shift: isize::MAX,
             sink: 0.into(),
+            shape: VertexShape::default(),
         }
     }
 
@@ -92,14 +107,16 @@ impl Vertex {
             align: align_root_sink,
             shift: isize::MAX,
             sink: align_root_sink,
+            shape: VertexShape::default(),
         }
     }
 
     #[cfg(test)]
     pub fn new_with_rank(rank: i32) -> Self {
-        let mut v = Self::default();
-        v.rank = rank;
-        v
+        Self {
+            rank,
+            ..Default::default()
+        }
     }
 }
 
@@ -118,6 +135,7 @@ impl Default for Vertex {
             align: 0.into(),
             shift: isize::MAX,
             sink: 0.into(),
+            shape: VertexShape::default(),
         }
     }
 }
@@ -128,6 +146,21 @@ pub(super) struct Edge {
     cut_value: Option<i32>,
     is_tree_edge: bool,
     has_type_1_conflict: bool,
+    is_reversed: bool,
+    /// Minimum length of this edge, if it is larger than the minimum length
+    /// passed to the ranking. Only used in the auxiliary graph of the
+    /// coordinate assignment via network simplex.
+    min_length: i32,
+}
+
+impl Edge {
+    #[cfg(feature = "dot")]
+    pub(super) fn new(weight: i32) -> Self {
+        Self {
+            weight,
+            ..Default::default()
+        }
+    }
 }
 
 impl Default for Edge {
@@ -137,6 +170,37 @@ impl Default for Edge {
             cut_value: None,
             is_tree_edge: false,
             has_type_1_conflict: false,
+            is_reversed: false,
+            min_length: 0,
+        }
+    }
+}
+
+/// The progress callback and the cancellation token of a build.
+#[derive(Clone, Default)]
+pub(crate) struct Control {
+    pub(crate) progress: Option<Arc<dyn Fn(Progress) + Send + Sync>>,
+    pub(crate) cancellation: Option<CancellationToken>,
+}
+
+impl Control {
+    fn report(&self, phase: Phase, iteration: usize) {
+        if let Some(progress) = &self.progress {
+            progress(Progress { phase, iteration });
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
         }
     }
 }
@@ -144,7 +208,11 @@ impl Default for Edge {
 pub(super) fn _build_layout_from_edges(edges: &[(u32, u32)], config: Config) -> Layouts<usize> {
     let graph = StableDiGraph::<Vertex, Edge>::from_edges(edges);
     // initialize vertex ids to NodeIndex
-    start(graph, config)
+    start(graph, config, &Control::default())
+        .unwrap_or_default()
+        .into_iter()
+        .map(Drawing::into_layout)
+        .collect()
 }
 
 pub(super) fn _build_layout_from_graph<T, E>(
@@ -153,14 +221,22 @@ pub(super) fn _build_layout_from_graph<T, E>(
 ) -> Layouts<usize> {
     // does this guarantee that ids will match?
     let algo_graph = graph.map(|_, _| Vertex::default(), |_, _| Edge::default());
-    start(algo_graph, config)
+    start(algo_graph, config, &Control::default())
+        .unwrap_or_default()
+        .into_iter()
+        .map(Drawing::into_layout)
+        .collect()
 }
 
-pub(super) fn start(mut graph: StableDiGraph<Vertex, Edge>, config: Config) -> Layouts<usize> {
+pub(super) fn start(
+    mut graph: StableDiGraph<Vertex, Edge>,
+    config: Config,
+    control: &Control,
+) -> Result<Drawings<usize>, Cancelled> {
     init_graph(&mut graph);
     weakly_connected_components(graph)
         .into_iter()
-        .map(|g| build_layout(g, config))
+        .map(|g| build_layout(g, config, control))
         .collect()
 }
 
@@ -178,39 +254,105 @@ fn init_graph(graph: &mut StableDiGraph<Vertex, Edge>) {
     }
 }
 
-fn build_layout(mut graph: StableDiGraph<Vertex, Edge>, config: Config) -> Layout {
+fn build_layout(
+    mut graph: StableDiGraph<Vertex, Edge>,
+    config: Config,
+    control: &Control,
+) -> Result<Drawing<usize>, Cancelled> {
     info!(target: "layouting", "Start building layout");
     info!(target: "layouting", "Configuration is: {:?}", config);
-    // we don't remember the edges that where reversed for now, since they are
-    // currently not needed
-    let _ = execute_phase_0(&mut graph);
-
+    control.check()?;
+    control.report(Phase::CycleRemoval, 0);
+    // reversed edges are marked via `Edge::is_reversed`, so they can be turned
+    // around again when the edge routes are calculated
+    let reversed_edges = execute_phase_0(&mut graph, config.cycle_removal).len();
+
+    control.check()?;
+    control.report(Phase::Ranking, 0);
     execute_phase_1(
         &mut graph,
         config.minimum_length as i32,
         config.ranking_type,
+        config.balance_ranks,
+        config.search_size,
+        config.max_simplex_iterations,
+        control,
     );
-
-    let layers = execute_phase_2(
+    let metrics = Metrics {
+        reversed_edges,
+        ..rank_metrics(&graph)
+    };
+
+    control.check()?;
+    control.report(Phase::CrossingReduction, 0);
+    let (layers, crossings, crossings_optimal) = execute_phase_2(
         &mut graph,
-        config.minimum_length as i32,
         config.dummy_vertices,
         config.c_minimization,
         config.transpose,
+        sweep_options(config),
+        control,
     );
 
-    let layout = execute_phase_3(&mut graph, layers, config.vertex_spacing, config.dummy_size);
+    control.check()?;
+    control.report(Phase::Coordinates, 0);
+    let mut layout = execute_phase_3(&mut graph, layers, config);
+    layout.crossings_optimal = crossings_optimal;
+    let Metrics {
+        bends,
+        width,
+        height,
+        ..
+    } = layout.metrics;
+    layout.metrics = Metrics {
+        crossings,
+        bends,
+        width,
+        height,
+        ..metrics
+    };
     debug!(target: "layouting", "Coordinates: {:?}\nwidth: {}, height:{}",
-        layout.0,
-        layout.1,
-        layout.2
+        layout.vertices,
+        layout.width,
+        layout.height
     );
-    layout
+    control.check()?;
+    Ok(layout)
 }
 
-fn execute_phase_0(graph: &mut StableDiGraph<Vertex, Edge>) -> Vec<EdgeIndex> {
+fn execute_phase_0(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    cycle_removal: CycleRemoval,
+) -> Vec<EdgeIndex> {
     info!(target: "layouting", "Executing phase 0: Cycle Removal");
-    p0::remove_cycles(graph)
+    p0::remove_cycles(graph, cycle_removal)
+}
+
+/// Measures the edges of the ranked graph before dummy vertices are inserted,
+/// i.e. the length of each edge is the difference of the ranks of its end
+/// points, which is the number of dummy vertices it needs plus one.
+fn rank_metrics(graph: &StableDiGraph<Vertex, Edge>) -> Metrics {
+    let max_rank = graph.node_weights().map(|w| w.rank).max().unwrap_or(0);
+    let mut layer_widths = vec![0; max_rank as usize + 1];
+    for w in graph.node_weights() {
+        layer_widths[w.rank as usize] += 1;
+    }
+    let mut lengths = Vec::new();
+    for edge in graph.edge_indices() {
+        let (tail, head) = graph.edge_endpoints(edge).unwrap();
+        let (tail, head) = (graph[tail].rank, graph[head].rank);
+        for rank in tail.min(head) + 1..tail.max(head) {
+            layer_widths[rank as usize] += 1;
+        }
+        lengths.push(tail.abs_diff(head) as usize);
+    }
+    Metrics {
+        total_edge_length: lengths.iter().sum(),
+        max_edge_length: lengths.iter().copied().max().unwrap_or(0),
+        dummy_vertices: lengths.iter().map(|l| l.saturating_sub(1)).sum(),
+        max_layer_width: layer_widths.into_iter().max().unwrap_or(0),
+        ..Default::default()
+    }
 }
 
 /// Assign each vertex a rank
@@ -218,19 +360,33 @@ fn execute_phase_1(
     graph: &mut StableDiGraph<Vertex, Edge>,
     minimum_length: i32,
     ranking_type: RankingType,
+    balance_ranks: bool,
+    search_size: usize,
+    max_simplex_iterations: usize,
+    control: &Control,
 ) {
     info!(target: "layouting", "Executing phase 1: Ranking");
-    p1::rank(graph, minimum_length, ranking_type);
+    p1::rank(
+        graph,
+        minimum_length,
+        ranking_type,
+        balance_ranks,
+        search_size,
+        max_simplex_iterations,
+        control,
+    );
 }
 
-/// Reorder vertices in ranks to reduce crossings
+/// Reorder vertices in ranks to reduce crossings, also returns the number of
+/// crossings and whether it is optimal
 fn execute_phase_2(
     graph: &mut StableDiGraph<Vertex, Edge>,
-    minimum_length: i32,
     dummy_vertices: bool,
     crossing_minimization: CrossingMinimization,
     transpose: bool,
-) -> Vec<Vec<NodeIndex>> {
+    options: p2::SweepOptions,
+    control: &Control,
+) -> (Vec<Vec<NodeIndex>>, usize, bool) {
     info!(target: "layouting", "Executing phase 2: Crossing Reduction");
     info!(target: "layouting",
         "Has dummy vertices: {}, heuristic for crossing minimization: {:?}, using transpose: {}",
@@ -239,22 +395,38 @@ fn execute_phase_2(
         transpose
     );
 
-    p2::insert_dummy_vertices(graph, minimum_length);
-    let mut order = p2::ordering(graph, crossing_minimization, transpose);
+    p2::insert_dummy_vertices(graph);
+    let (mut order, optimal) =
+        p2::ordering(graph, crossing_minimization, transpose, options, control);
+    let crossings = p2::crossings(graph, &order);
     if !dummy_vertices {
         p2::remove_dummy_vertices(graph, &mut order);
     }
-    order
+    (order, crossings, optimal)
+}
+
+fn sweep_options(config: Config) -> p2::SweepOptions {
+    p2::SweepOptions {
+        max_sweeps: config.max_sweeps,
+        patience: config.sweep_patience,
+        transpose_passes: config.transpose_passes,
+        time_budget: config.crossing_time_budget,
+        restarts: config.crossing_restarts,
+        seed: config.seed,
+        exact: config.exact_crossings,
+        exact_threshold: config.exact_crossing_threshold,
+    }
 }
 
 /// calculate the final coordinates for each vertex, after the graph was layered and crossings where minimized.
 fn execute_phase_3(
     graph: &mut StableDiGraph<Vertex, Edge>,
     mut layers: Vec<Vec<NodeIndex>>,
-    vertex_spacing: usize,
-    dummy_size: f64,
-) -> Layout {
-    info!(target: "layouting", "Executing phase 3: Coordinate Calculation");
+    config: Config,
+) -> Drawing<usize> {
+    let vertex_spacing = config.vertex_spacing;
+    let dummy_size = config.dummy_size;
+    info!(target: "layouting", "Executing phase 3: Coordinate Calculation, {:?}", config.coordinate_assignment);
     info!(target: "layouting", "Dummy vertices size (if enabled): {dummy_size}");
     for n in graph.node_indices().collect::<Vec<_>>() {
         if graph[n].is_dummy {
@@ -263,80 +435,191 @@ fn execute_phase_3(
     }
     let width = layers.iter().map(|l| l.len()).max().unwrap_or(0);
     let height = layers.len();
+    let vertices = graph.node_indices().collect::<Vec<_>>();
     let mut layouts = p3::create_layouts(graph, &mut layers, vertex_spacing, dummy_size);
-
-    p3::align_to_smallest_width_layout(&mut layouts);
-    let mut x_coordinates = p3::calculate_relative_coords(layouts);
+    p3::align_to_smallest_width_layout(&mut layouts, &vertices);
+    let mut x_coordinates =
+        p3::calculate_relative_coords(layouts, &vertices, config.coordinate_combination);
+    if config.coordinate_assignment == CoordinateAssignment::NetworkSimplex {
+        // the layout of Brandes and Koepf is the initial solution
+        x_coordinates = p3::network_simplex_coordinates(
+            graph,
+            &layers,
+            &x_coordinates,
+            vertex_spacing,
+            dummy_size,
+            config.search_size,
+            config.max_simplex_iterations,
+        );
+    }
+    if config.balance_coordinates {
+        p3::balance_coordinates(
+            graph,
+            &layers,
+            &mut x_coordinates,
+            vertex_spacing,
+            dummy_size,
+        );
+    }
+    if config.straighten_passes > 0 {
+        p3::straighten(
+            graph,
+            &layers,
+            &mut x_coordinates,
+            vertex_spacing,
+            dummy_size,
+            config.straighten_passes,
+        );
+    }
     // determine the smallest x-coordinate
-    let min = x_coordinates.iter().min_by(|a, b| a.1.cmp(&b.1)).unwrap().1;
-
-    // shift all coordinates so the minimum coordinate is 0
-    for (_, c) in &mut x_coordinates {
-        *c -= min;
+    let min = vertices
+        .iter()
+        .map(|v| x_coordinates[v.index()])
+        .min()
+        .unwrap();
+
+    // shift all coordinates so the minimum coordinate is 0 and
+    // calculate y coordinate
+    let (coordinates, mut edges) = match config.edge_routing {
+        EdgeRouting::Polyline | EdgeRouting::Spline => {
+            let coordinates = x_coordinates
+                .into_iter()
+                .enumerate()
+                .map(|(v, x)| {
+                    let rank = graph.node_weight(NodeIndex::new(v)).map_or(0, |w| w.rank);
+                    (x - min, -(rank as isize * vertex_spacing as isize))
+                })
+                .collect::<Vec<_>>();
+            let edges = p4::polyline_routes(graph, &coordinates);
+            (coordinates, edges)
+        }
+        EdgeRouting::Orthogonal => {
+            let x_coordinates = x_coordinates
+                .into_iter()
+                .map(|x| x - min)
+                .collect::<Vec<_>>();
+            p4::orthogonal_routes(graph, &layers, &x_coordinates, vertex_spacing)
+        }
+    };
+    // splines are smooth, so their bends are those of the polyline they follow
+    let bends = edges.iter().map(|e| p4::count_bends(&e.points)).sum();
+    if config.edge_routing == EdgeRouting::Spline {
+        p4::spline_routes(graph, &mut edges, vertex_spacing);
     }
+    p4::clip_routes(
+        graph,
+        &mut edges,
+        config.edge_routing == EdgeRouting::Spline,
+    );
+
+    // each vertex takes up the vertex spacing in both directions
+    let points = vertices
+        .iter()
+        .map(|v| coordinates[v.index()])
+        .chain(edges.iter().flat_map(|e| e.points.iter().copied()));
+    let extent = |coordinate: fn((isize, isize)) -> isize| {
+        let (min, max) = points
+            .clone()
+            .map(coordinate)
+            .fold((isize::MAX, isize::MIN), |(min, max), c| {
+                (min.min(c), max.max(c))
+            });
+        (max - min).max(0) as usize + vertex_spacing
+    };
+    let metrics = Metrics {
+        bends,
+        width: extent(|(x, _)| x),
+        height: extent(|(_, y)| y),
+        ..Default::default()
+    };
 
-    let mut v = x_coordinates.iter().collect::<Vec<_>>();
-    v.sort_by(|a, b| a.0.index().cmp(&b.0.index()));
     // format to NodeIndex: (x, y), width, height
-    (
-        x_coordinates
+    Drawing {
+        vertices: vertices
             .into_iter()
-            .filter(|(v, _)| !graph[*v].is_dummy)
-            // calculate y coordinate
-            .map(|(v, x)| {
-                (
-                    graph[v].id,
-                    (x, -(graph[v].rank as isize * vertex_spacing as isize)),
-                )
-            })
+            .filter(|v| !graph[*v].is_dummy)
+            .map(|v| (graph[v].id, coordinates[v.index()]))
             .collect::<Vec<_>>(),
+        edges,
         width,
         height,
-    )
+        crossings_optimal: false,
+        metrics,
+    }
 }
 
 fn slack(graph: &StableDiGraph<Vertex, Edge>, edge: EdgeIndex, minimum_length: i32) -> i32 {
     let (tail, head) = graph.edge_endpoints(edge).unwrap();
-    graph[head].rank - graph[tail].rank - minimum_length
+    graph[head].rank - graph[tail].rank - minimum_length.max(graph[edge].min_length)
 }
 
-#[allow(dead_code)]
-fn print_to_console(
-    dir: VDir,
-    graph: &StableDiGraph<Vertex, Edge>,
-    layers: &[Vec<NodeIndex>],
-    mut coordinates: HashMap<NodeIndex, isize>,
-    vertex_spacing: usize,
-) {
-    let min = *coordinates.values().min().unwrap();
-    let str_width = 4;
-    coordinates
-        .values_mut()
-        .for_each(|v| *v = str_width * (*v - min) / vertex_spacing as isize);
-    let width = *coordinates.values().max().unwrap() as usize;
-
-    for line in layers {
-        let mut v_line = vec!['-'; width + str_width as usize];
-        let mut a_line = vec![' '; width + str_width as usize];
-        for v in line {
-            let pos = *coordinates.get(v).unwrap() as usize;
-            if graph[*v].root != *v {
-                a_line[pos] = if dir == VDir::Up { 'v' } else { '^' };
-            }
-            for (i, c) in v.index().to_string().chars().enumerate() {
-                v_line[pos + i] = c;
-            }
-        }
-        match dir {
-            VDir::Up => {
-                println!("{}", v_line.into_iter().collect::<String>());
-                println!("{}", a_line.into_iter().collect::<String>());
-            }
-            VDir::Down => {
-                println!("{}", a_line.into_iter().collect::<String>());
-                println!("{}", v_line.into_iter().collect::<String>());
-            }
+#[cfg(test)]
+mod benchmark {
+    use std::time::{Duration, Instant};
+
+    use petgraph::stable_graph::StableDiGraph;
+
+    use crate::{configure::Config, util::weakly_connected_components};
+
+    use super::{
+        execute_phase_0, execute_phase_1, execute_phase_2, execute_phase_3, init_graph,
+        sweep_options, Control, Edge, Vertex,
+    };
+
+    /// Prints the time spent in each phase, so changes to a single phase can
+    /// be compared. See the README for the numbers before and after phases 2
+    /// and 3 were moved from hash maps to vectors.
+    fn time_phases(edges: &[(u32, u32)]) {
+        let config = Config::default();
+        let mut graph = StableDiGraph::<Vertex, Edge>::from_edges(edges);
+        init_graph(&mut graph);
+        let mut times = [Duration::ZERO; 3];
+        for mut graph in weakly_connected_components(graph) {
+            execute_phase_0(&mut graph, config.cycle_removal);
+            let start = Instant::now();
+            execute_phase_1(
+                &mut graph,
+                config.minimum_length as i32,
+                config.ranking_type,
+                config.balance_ranks,
+                config.search_size,
+                config.max_simplex_iterations,
+                &Control::default(),
+            );
+            times[0] += start.elapsed();
+
+            let start = Instant::now();
+            let (layers, ..) = execute_phase_2(
+                &mut graph,
+                config.dummy_vertices,
+                config.c_minimization,
+                config.transpose,
+                sweep_options(config),
+                &Control::default(),
+            );
+            times[1] += start.elapsed();
+
+            let start = Instant::now();
+            execute_phase_3(&mut graph, layers, config);
+            times[2] += start.elapsed();
         }
+        println!(
+            "ranking: {}ms, crossing reduction: {}ms, coordinates: {}ms",
+            times[0].as_millis(),
+            times[1].as_millis(),
+            times[2].as_millis()
+        );
+    }
+
+    #[test]
+    fn l_4000_2_phases() {
+        let edges = graph_generator::GraphLayout::new_from_num_nodes(4000, 2).build_edges();
+        time_phases(&edges);
+    }
+
+    #[test]
+    fn l_8000_2_phases() {
+        let edges = graph_generator::GraphLayout::new_from_num_nodes(8000, 2).build_edges();
+        time_phases(&edges);
     }
-    println!();
 }
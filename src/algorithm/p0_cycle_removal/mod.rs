@@ -1,26 +1,53 @@
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
 use log::{debug, info};
 use petgraph::{
-    algo::{greedy_feedback_arc_set, is_cyclic_directed},
-    stable_graph::{EdgeIndex, StableDiGraph},
+    algo::{greedy_feedback_arc_set, is_cyclic_directed, tarjan_scc},
+    stable_graph::{EdgeIndex, NodeIndex, StableDiGraph},
     visit::EdgeRef,
+    Direction::Outgoing,
 };
 
+use crate::configure::CycleRemoval;
+
 use super::{Edge, Vertex};
 
+/// Strongly connected components with more vertices than this are not
+/// solved exactly, since the runtime grows exponentially.
+pub(crate) static EXACT_MAX_VERTICES: usize = 16;
+
 /// Removes all the edges that contribute to cycles in the graph
-/// Does so by finding a greedy feedback arc set and then reversing the
-/// direction of the edges from that set.
-/// Is not guaranteed to find the minimum fas.
-pub(crate) fn remove_cycles(graph: &mut StableDiGraph<Vertex, Edge>) -> Vec<EdgeIndex> {
+/// Does so by finding a feedback arc set with the given strategy and then
+/// reversing the direction of the edges from that set.
+/// Only [CycleRemoval::Exact] is guaranteed to find the minimum fas, and only
+/// for small strongly connected components.
+pub(crate) fn remove_cycles(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    cycle_removal: CycleRemoval,
+) -> Vec<EdgeIndex> {
     if !is_cyclic_directed(&*graph) {
         info!(target: "Cycle Removal", "Graph contains no cycle");
         return Vec::new();
     }
 
-    info!(target: "Cycle Removal", "Graph contains cycle, reversing edges");
+    info!(target: "Cycle Removal", "Graph contains cycle, reversing edges using {cycle_removal:?}");
 
     // get the feedback arc set
-    let fas: Vec<EdgeIndex> = greedy_feedback_arc_set(&*graph).map(|e| e.id()).collect();
+    let fas: Vec<EdgeIndex> = match cycle_removal {
+        CycleRemoval::Greedy => greedy_feedback_arc_set(&*graph).map(|e| e.id()).collect(),
+        CycleRemoval::DepthFirstSearch => dfs_back_edges(graph),
+        CycleRemoval::WeightedGreedy => backward_edges(graph, weighted_greedy_order),
+        CycleRemoval::Exact => backward_edges(graph, |c| {
+            if c.len() <= EXACT_MAX_VERTICES {
+                exact_order(c)
+            } else {
+                info!(target: "Cycle Removal",
+                    "Component with {} vertices is too large to be solved exactly, using weighted greedy",
+                    c.len());
+                weighted_greedy_order(c)
+            }
+        }),
+    };
     let mut reversed_edges = Vec::new();
 
     // reverse the direction of the edges
@@ -29,7 +56,14 @@ pub(crate) fn remove_cycles(graph: &mut StableDiGraph<Vertex, Edge>) -> Vec<Edge
             // get the weight
             let weight = graph[edge];
             // add new edge in reversed direction
-            let reversed_edge = graph.add_edge(head, tail, weight);
+            let reversed_edge = graph.add_edge(
+                head,
+                tail,
+                Edge {
+                    is_reversed: true,
+                    ..weight
+                },
+            );
             reversed_edges.push(reversed_edge);
             // remove the old edge
             graph.remove_edge(edge);
@@ -43,25 +77,276 @@ pub(crate) fn remove_cycles(graph: &mut StableDiGraph<Vertex, Edge>) -> Vec<Edge
     reversed_edges
 }
 
+/// Returns the outgoing edges of a vertex in the order they where inserted.
+fn outgoing_in_order(
+    graph: &StableDiGraph<Vertex, Edge>,
+    v: NodeIndex,
+) -> Vec<(EdgeIndex, NodeIndex)> {
+    let mut edges = graph
+        .edges_directed(v, Outgoing)
+        .map(|e| (e.id(), e.target()))
+        .collect::<Vec<_>>();
+    edges.sort_by_key(|(e, _)| *e);
+    edges
+}
+
+/// Returns the back edges of a depth first search, which visits vertices and
+/// edges in the order they where inserted into the graph.
+fn dfs_back_edges(graph: &StableDiGraph<Vertex, Edge>) -> Vec<EdgeIndex> {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum State {
+        Unvisited,
+        OnStack,
+        Done,
+    }
+
+    let mut state = HashMap::new();
+    let mut back_edges = Vec::new();
+    for root in graph.node_indices() {
+        if state.contains_key(&root) {
+            continue;
+        }
+        state.insert(root, State::OnStack);
+        let mut stack = vec![(root, outgoing_in_order(graph, root), 0)];
+        while let Some((v, edges, next)) = stack.last_mut() {
+            let Some(&(edge, head)) = edges.get(*next) else {
+                state.insert(*v, State::Done);
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+            match state.get(&head).copied().unwrap_or(State::Unvisited) {
+                State::Unvisited => {
+                    state.insert(head, State::OnStack);
+                    stack.push((head, outgoing_in_order(graph, head), 0));
+                }
+                State::OnStack => back_edges.push(edge),
+                State::Done => (),
+            }
+        }
+    }
+    back_edges
+}
+
+/// A strongly connected component, with vertices and edges indexed locally.
+struct Component {
+    /// outgoing edges with their weight
+    outgoing: Vec<Vec<(usize, i64)>>,
+    /// incoming edges with their weight
+    incoming: Vec<Vec<(usize, i64)>>,
+}
+
+impl Component {
+    fn len(&self) -> usize {
+        self.outgoing.len()
+    }
+}
+
+/// Orders the vertices of each strongly connected component with `order` and
+/// returns all the edges pointing backwards in that order.
+fn backward_edges(
+    graph: &StableDiGraph<Vertex, Edge>,
+    order: impl Fn(&Component) -> Vec<usize>,
+) -> Vec<EdgeIndex> {
+    let mut fas = Vec::new();
+    for mut scc in tarjan_scc(graph) {
+        if scc.len() < 2 {
+            continue;
+        }
+        // keep the input order, so ties are broken consistently
+        scc.sort();
+        let index = scc
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (*v, i))
+            .collect::<HashMap<_, _>>();
+        let mut edges = Vec::new();
+        let mut component = Component {
+            outgoing: vec![Vec::new(); scc.len()],
+            incoming: vec![Vec::new(); scc.len()],
+        };
+        for (i, v) in scc.iter().enumerate() {
+            for (edge, head) in outgoing_in_order(graph, *v) {
+                let Some(&j) = index.get(&head) else {
+                    continue;
+                };
+                if i != j {
+                    let weight = graph[edge].weight as i64;
+                    component.outgoing[i].push((j, weight));
+                    component.incoming[j].push((i, weight));
+                    edges.push((edge, i, j));
+                }
+            }
+        }
+
+        let mut position = vec![0; scc.len()];
+        for (p, v) in order(&component).into_iter().enumerate() {
+            position[v] = p;
+        }
+        fas.extend(
+            edges
+                .into_iter()
+                .filter(|(_, tail, head)| position[*tail] > position[*head])
+                .map(|(e, _, _)| e),
+        );
+    }
+    fas
+}
+
+/// Orders the vertices with the heuristic of Eades, Lin and Smyth, using the
+/// weights of the edges: Sinks are moved to the end, sources to the start,
+/// and otherwise the vertex with the largest difference between the weight of
+/// its outgoing and incoming edges is moved to the start.
+fn weighted_greedy_order(component: &Component) -> Vec<usize> {
+    let n = component.len();
+    let mut in_degree = component.incoming.iter().map(Vec::len).collect::<Vec<_>>();
+    let mut out_degree = component.outgoing.iter().map(Vec::len).collect::<Vec<_>>();
+    let sum = |edges: &Vec<(usize, i64)>| edges.iter().map(|(_, w)| w).sum::<i64>();
+    let mut delta = (0..n)
+        .map(|v| sum(&component.outgoing[v]) - sum(&component.incoming[v]))
+        .collect::<Vec<_>>();
+    let mut removed = vec![false; n];
+
+    let mut sinks = (0..n)
+        .filter(|v| out_degree[*v] == 0)
+        .collect::<VecDeque<_>>();
+    let mut sources = (0..n)
+        .filter(|v| in_degree[*v] == 0)
+        .collect::<VecDeque<_>>();
+    // prefer vertices that where inserted first if deltas are equal
+    let mut heap = (0..n)
+        .map(|v| (delta[v], std::cmp::Reverse(v)))
+        .collect::<BinaryHeap<_>>();
+
+    let mut start = Vec::new();
+    let mut end = Vec::new();
+    while start.len() + end.len() < n {
+        let v = if let Some(v) = sinks.pop_front() {
+            if removed[v] {
+                continue;
+            }
+            end.push(v);
+            v
+        } else if let Some(v) = sources.pop_front() {
+            if removed[v] {
+                continue;
+            }
+            start.push(v);
+            v
+        } else {
+            let (d, std::cmp::Reverse(v)) = heap.pop().unwrap();
+            if removed[v] || d != delta[v] {
+                continue;
+            }
+            start.push(v);
+            v
+        };
+        removed[v] = true;
+
+        for &(head, weight) in &component.outgoing[v] {
+            if !removed[head] {
+                in_degree[head] -= 1;
+                delta[head] += weight;
+                heap.push((delta[head], std::cmp::Reverse(head)));
+                if in_degree[head] == 0 {
+                    sources.push_back(head);
+                }
+            }
+        }
+        for &(tail, weight) in &component.incoming[v] {
+            if !removed[tail] {
+                out_degree[tail] -= 1;
+                delta[tail] -= weight;
+                heap.push((delta[tail], std::cmp::Reverse(tail)));
+                if out_degree[tail] == 0 {
+                    sinks.push_back(tail);
+                }
+            }
+        }
+    }
+
+    start.extend(end.into_iter().rev());
+    start
+}
+
+/// Finds the order with the minimum weight of backward edges, via dynamic
+/// programming over all subsets of vertices.
+fn exact_order(component: &Component) -> Vec<usize> {
+    let n = component.len();
+    let full = (1usize << n) - 1;
+    let mut cost = vec![i64::MAX; full + 1];
+    let mut last = vec![0; full + 1];
+    cost[0] = 0;
+
+    // cost[set] is the minimum weight of backward edges, if the vertices in
+    // set are placed first. Placing v after set reverses its edges into set.
+    for set in 0..full {
+        if cost[set] == i64::MAX {
+            continue;
+        }
+        for v in (0..n).filter(|v| set & (1 << v) == 0) {
+            let backward = component.outgoing[v]
+                .iter()
+                .filter(|(head, _)| set & (1 << head) != 0)
+                .map(|(_, w)| w)
+                .sum::<i64>();
+            let next = set | (1 << v);
+            if cost[set] + backward < cost[next] {
+                cost[next] = cost[set] + backward;
+                last[next] = v;
+            }
+        }
+    }
+
+    let mut order = Vec::with_capacity(n);
+    let mut set = full;
+    while set != 0 {
+        order.push(last[set]);
+        set &= !(1 << last[set]);
+    }
+    order.reverse();
+    order
+}
+
 #[cfg(test)]
 mod tests {
     use petgraph::{algo::is_cyclic_directed, stable_graph::StableDiGraph};
 
     use crate::algorithm::{Edge, Vertex};
+    use crate::configure::CycleRemoval;
 
     use super::remove_cycles;
 
+    static STRATEGIES: [CycleRemoval; 4] = [
+        CycleRemoval::Greedy,
+        CycleRemoval::DepthFirstSearch,
+        CycleRemoval::WeightedGreedy,
+        CycleRemoval::Exact,
+    ];
+
+    fn reversed_pairs(graph: &StableDiGraph<Vertex, Edge>) -> Vec<(usize, usize)> {
+        let mut pairs = graph
+            .edge_indices()
+            .filter(|e| graph[*e].is_reversed)
+            .map(|e| {
+                let (head, tail) = graph.edge_endpoints(e).unwrap();
+                (tail.index(), head.index())
+            })
+            .collect::<Vec<_>>();
+        pairs.sort();
+        pairs
+    }
+
     #[test]
     fn test_graph_simple_no_cycles() {
-        let mut graph =
-            StableDiGraph::<Vertex, Edge>::from_edges(&[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        let mut graph = StableDiGraph::<Vertex, Edge>::from_edges([(0, 1), (0, 2), (1, 3), (2, 3)]);
         assert!(!is_cyclic_directed(&graph));
-        assert!(remove_cycles(&mut graph).is_empty());
+        assert!(remove_cycles(&mut graph, CycleRemoval::Greedy).is_empty());
     }
 
     #[test]
     fn test_graph_simple_contains_cycle() {
-        let mut graph = StableDiGraph::<Vertex, Edge>::from_edges(&[
+        let mut graph = StableDiGraph::<Vertex, Edge>::from_edges([
             (2, 1),
             (1, 4),
             (4, 5),
@@ -72,13 +357,13 @@ mod tests {
         ]);
 
         assert!(is_cyclic_directed(&graph));
-        let _ = remove_cycles(&mut graph);
+        let _ = remove_cycles(&mut graph, CycleRemoval::Greedy);
         assert!(!is_cyclic_directed(&graph));
     }
 
     #[test]
     fn test_graph_complex_contains_cycle() {
-        let mut graph = StableDiGraph::<Vertex, Edge>::from_edges(&[
+        let mut graph = StableDiGraph::<Vertex, Edge>::from_edges([
             (1, 2),
             (2, 5),
             (2, 6),
@@ -96,11 +381,85 @@ mod tests {
         ]);
 
         assert!(is_cyclic_directed(&graph));
-        let edges = remove_cycles(&mut graph);
+        let edges = remove_cycles(&mut graph, CycleRemoval::Greedy);
         println!(
             "test_graph_complex_contains_cycle: Reversed {} edges",
             edges.len()
         );
         assert!(!is_cyclic_directed(&graph));
     }
+
+    #[test]
+    fn all_strategies_remove_cycles() {
+        let edges = [
+            (1, 2),
+            (2, 5),
+            (2, 6),
+            (2, 3),
+            (3, 4),
+            (4, 3),
+            (4, 8),
+            (8, 4),
+            (8, 7),
+            (3, 7),
+            (6, 7),
+            (7, 6),
+            (5, 6),
+            (5, 1),
+        ];
+        for strategy in STRATEGIES {
+            let mut graph = StableDiGraph::<Vertex, Edge>::from_edges(edges);
+            let reversed = remove_cycles(&mut graph, strategy);
+            assert!(!reversed.is_empty());
+            assert!(!is_cyclic_directed(&graph), "{strategy:?}");
+            assert_eq!(graph.edge_count(), edges.len());
+        }
+    }
+
+    #[test]
+    fn dfs_respects_input_order() {
+        let mut graph = StableDiGraph::<Vertex, Edge>::from_edges([(0, 1), (1, 2), (2, 0)]);
+        remove_cycles(&mut graph, CycleRemoval::DepthFirstSearch);
+        assert_eq!(reversed_pairs(&graph), [(2, 0)]);
+    }
+
+    #[test]
+    fn weighted_strategies_keep_heavy_edges() {
+        // reversing (0, 1) breaks both cycles, but is more expensive than
+        // reversing (1, 0) and (2, 0)
+        let edges = [(0, 1, 3), (1, 2, 1), (2, 0, 1), (1, 0, 1)];
+        for strategy in [CycleRemoval::WeightedGreedy, CycleRemoval::Exact] {
+            let mut graph = StableDiGraph::<Vertex, Edge>::new();
+            let v = (0..3)
+                .map(|_| graph.add_node(Vertex::default()))
+                .collect::<Vec<_>>();
+            for (tail, head, weight) in edges {
+                graph.add_edge(
+                    v[tail],
+                    v[head],
+                    Edge {
+                        weight,
+                        ..Default::default()
+                    },
+                );
+            }
+            remove_cycles(&mut graph, strategy);
+            assert_eq!(reversed_pairs(&graph), [(1, 0), (2, 0)], "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn exact_finds_minimum() {
+        // a tournament on 5 vertices, whose minimum feedback arc set has size 3
+        let mut edges = Vec::new();
+        for i in 0..5u32 {
+            for j in [1, 2] {
+                edges.push((i, (i + j) % 5));
+            }
+        }
+        let mut graph = StableDiGraph::<Vertex, Edge>::from_edges(&edges);
+        let reversed = remove_cycles(&mut graph, CycleRemoval::Exact);
+        assert!(!is_cyclic_directed(&graph));
+        assert_eq!(reversed.len(), 3);
+    }
 }
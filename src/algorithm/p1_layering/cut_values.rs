@@ -14,7 +14,7 @@ struct NeighborhoodInfo {
     cut_value_sum: i32,
     tree_edge_weight_sum: i32,
     non_tree_edge_weight_sum: i32,
-    missing: Option<NodeIndex>,
+    missing: Option<(EdgeIndex, NodeIndex)>,
 }
 
 pub(super) fn init_cutvalues(graph: &mut StableDiGraph<Vertex, Edge>) {
@@ -65,19 +65,16 @@ fn calculate_cut_values(graph: &mut StableDiGraph<Vertex, Edge>, mut queue: VecD
             _ => continue,
         };
 
-        let missing = match (incoming.missing, outgoing.missing) {
+        // the tree edge itself is used, since there may be parallel edges
+        // between the same vertices, which are not part of the tree
+        let (edge, missing) = match (incoming.missing, outgoing.missing) {
             (Some(u), None) => u,
-            (None, Some(v)) => v,
-            _ => continue,
-        };
-
-        let edge = match graph.find_edge(vertex, missing) {
-            Some(e) => {
+            (None, Some(v)) => {
                 // switch direction, if vertex is tail component of edge
                 std::mem::swap(&mut incoming, &mut outgoing);
-                e
+                v
             }
-            None => graph.find_edge(missing, vertex).unwrap(),
+            _ => continue,
         };
 
         graph[edge].cut_value = Some(calculate_cut_value(graph[edge].weight, incoming, outgoing));
@@ -114,16 +111,16 @@ fn get_neighborhood_info(
     let mut non_tree_edge_weight_sum = 0;
     let mut missing = None;
 
-    for edge in graph.edges_directed(vertex, direction) {
-        let (tail, head) = (edge.source(), edge.target());
-        let edge = *edge.weight();
+    for edge_ref in graph.edges_directed(vertex, direction) {
+        let (tail, head) = (edge_ref.source(), edge_ref.target());
+        let edge = *edge_ref.weight();
         if !edge.is_tree_edge {
             non_tree_edge_weight_sum += edge.weight;
         } else if let Some(cut_value) = edge.cut_value {
             cut_value_sum += cut_value;
             tree_edge_weight_sum += edge.weight;
         } else if missing.is_none() {
-            missing = Some(if tail == vertex { head } else { tail });
+            missing = Some((edge_ref.id(), if tail == vertex { head } else { tail }));
         } else {
             return None;
         }
@@ -159,7 +156,7 @@ fn remove_outdated_cut_values(
         Some(mut parent) => {
             let mut l = w;
             loop {
-                let edge = graph.find_edge_undirected(l, parent).unwrap().0;
+                let edge = tree_edge(graph, l, parent, removed_edge);
                 graph[edge].cut_value = None;
                 l = parent;
                 trace!(target: "cut_values", "current node in path: {}", l.index());
@@ -178,7 +175,7 @@ fn remove_outdated_cut_values(
     let mut l = x;
     while l != least_common_ancestor {
         let parent = graph[l].parent.unwrap();
-        let edge = graph.find_edge_undirected(l, parent).unwrap().0;
+        let edge = tree_edge(graph, l, parent, removed_edge);
         graph[edge].cut_value = None;
         l = parent;
     }
@@ -192,6 +189,22 @@ fn remove_outdated_cut_values(
     least_common_ancestor
 }
 
+/// Returns the edge of the old tree connecting `u` and `v`, in either
+/// direction. Parallel edges which are not part of the tree are skipped.
+fn tree_edge(
+    graph: &StableDiGraph<Vertex, Edge>,
+    u: NodeIndex,
+    v: NodeIndex,
+    removed_edge: EdgeIndex,
+) -> EdgeIndex {
+    graph
+        .edges_connecting(u, v)
+        .chain(graph.edges_connecting(v, u))
+        .find(|e| e.weight().is_tree_edge || e.id() == removed_edge)
+        .unwrap()
+        .id()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -1,13 +1,16 @@
-// TODOS: Keep non graph edges during rank() procedure in vecdeque to be able to cyclically search through them
 //! Executes the second phase of sugiyamas algorithm, which assigns each vertex
 //! a rank.
 //! Currently three ranking algorithm are implmented:
 //!
 //! 1. Original - tries to move each vertex as close to neighbors as possible.
 //! 2. MinimizeEdgeLength - builds a feasible tight tree in order to minimize
-//! edge lengths. This is the technique describe in the paper by Gansner et al.
+//!    edge lengths. This is the technique describe in the paper by Gansner et al.
 //! 3. Up - Move vertices as far up as possible
 //! 4. Down - Move vertices as far down as possible.
+//! 5. CoffmanGraham - Limits the number of vertices in each layer, using the
+//!    algorithm by Coffman and Graham.
+//! 6. NodePromotion - Moves vertices as far up as possible and then moves
+//!    single vertices down again, if this reduces the number of dummy vertices.
 //!
 mod cut_values;
 mod low_lim;
@@ -15,44 +18,96 @@ pub(super) mod ranking;
 #[cfg(test)]
 pub(crate) mod tests;
 
-use log::info;
+use log::{debug, info};
 use petgraph::stable_graph::{EdgeIndex, StableDiGraph};
-use petgraph::visit::IntoNodeIdentifiers;
+use petgraph::visit::{EdgeRef, IntoNodeIdentifiers};
+use petgraph::Direction::{Incoming, Outgoing};
 
 use crate::configure::RankingType;
+use crate::Phase;
 
 use self::cut_values::update_cutvalues;
 use self::low_lim::update_low_lim;
-use self::ranking::{feasible_tree, init_rank, move_vertices_down, move_vertices_up, update_ranks};
+use self::ranking::{
+    balance_ranks, coffman_graham, feasible_tree, init_rank, move_vertices_down, move_vertices_up,
+    promote_vertices, subtree, update_subtree_ranks,
+};
 
-use super::{slack, Edge, Vertex};
+use super::{slack, Control, Edge, Vertex};
 
 pub(super) fn rank(
     graph: &mut StableDiGraph<Vertex, Edge>,
     minimum_length: i32,
     ranking_type: RankingType,
+    balance: bool,
+    search_size: usize,
+    max_iterations: usize,
+    control: &Control,
 ) {
-    info!(target: "ranking", "Start ranking, ranking type: {ranking_type:?}, minimum_length: {minimum_length}");
+    info!(target: "ranking", "Start ranking, ranking type: {ranking_type:?}, minimum_length: {minimum_length}, balance: {balance}");
     init_rank(graph, minimum_length);
     match ranking_type {
         RankingType::Original => original(graph, minimum_length),
-        RankingType::MinimizeEdgeLength => minimize_edge_length(graph, minimum_length),
+        RankingType::MinimizeEdgeLength => minimize_edge_length(
+            graph,
+            minimum_length,
+            balance,
+            search_size,
+            max_iterations,
+            control,
+        ),
         RankingType::Up => move_vertices_up(graph, minimum_length),
         RankingType::Down => move_vertices_down(graph, minimum_length),
+        RankingType::NodePromotion => {
+            move_vertices_up(graph, minimum_length);
+            promote_vertices(graph, minimum_length);
+        }
+        RankingType::CoffmanGraham { max_width } => {
+            coffman_graham(graph, minimum_length, max_width)
+        }
     }
 }
 
-fn minimize_edge_length(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
+/// Runs the network simplex, starting with the current ranks, which need to
+/// be feasible. Each iteration is reported to `control`, which stops the
+/// search if the build is cancelled.
+pub(super) fn minimize_edge_length(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    minimum_length: i32,
+    balance: bool,
+    search_size: usize,
+    max_iterations: usize,
+    control: &Control,
+) {
     feasible_tree(graph, minimum_length);
-    while let Some(removed_edge) = leave_edge(graph) {
+    // edges are neither added nor removed, so the search can continue
+    // where it stopped the last time
+    let edges = graph.edge_indices().collect::<Vec<_>>();
+    let mut search_start = 0;
+    let mut iterations = 0;
+    while let Some(removed_edge) = leave_edge(graph, &edges, &mut search_start, search_size) {
+        if iterations == max_iterations {
+            info!(target: "ranking", "Reached maximum number of iterations: {max_iterations}");
+            break;
+        }
+        if control.is_cancelled() {
+            info!(target: "ranking", "Cancelled after {iterations} iterations");
+            break;
+        }
         // swap edges and calculate cut value
         let swap_edge = enter_edge(graph, removed_edge, minimum_length);
         exchange(graph, removed_edge, swap_edge, minimum_length);
+        iterations += 1;
+        control.report(Phase::Ranking, iterations);
     }
+    debug!(target: "ranking", "Network simplex finished after {iterations} iterations");
 
-    // don't balance ranks since we want maximum width to
+    // by default don't balance ranks since we want maximum width to
     // give indication about number of parallel processes running
     normalize(graph);
+    if balance {
+        balance_ranks(graph, minimum_length);
+    }
 }
 
 fn original(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
@@ -60,15 +115,37 @@ fn original(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
     move_vertices_down(graph, minimum_length);
 }
 
-fn leave_edge(graph: &StableDiGraph<Vertex, Edge>) -> Option<EdgeIndex> {
-    for edge in graph.edge_indices() {
-        if let Some(cut_value) = graph[edge].cut_value {
-            if cut_value < 0 {
-                return Some(edge);
-            }
+/// Searches for a tree edge with a negative cut value, like Graphviz does.
+///
+/// The search starts at `search_start` and wraps around, so edges at the
+/// beginning aren't looked at over and over again. It stops after
+/// `search_size` candidates where found and returns the one with the lowest
+/// cut value. `search_start` is set to the edge after the last one looked at.
+fn leave_edge(
+    graph: &StableDiGraph<Vertex, Edge>,
+    edges: &[EdgeIndex],
+    search_start: &mut usize,
+    search_size: usize,
+) -> Option<EdgeIndex> {
+    let mut candidate: Option<(EdgeIndex, i32)> = None;
+    let mut found = 0;
+    for i in 0..edges.len() {
+        let i = (*search_start + i) % edges.len();
+        let edge = edges[i];
+        let cut_value = match graph[edge].cut_value {
+            Some(cut_value) if graph[edge].is_tree_edge && cut_value < 0 => cut_value,
+            _ => continue,
+        };
+        if candidate.is_none_or(|(_, c)| cut_value < c) {
+            candidate = Some((edge, cut_value));
+        }
+        found += 1;
+        if found >= search_size {
+            *search_start = i + 1;
+            return candidate.map(|(e, _)| e);
         }
     }
-    None
+    candidate.map(|(e, _)| e)
 }
 
 fn enter_edge(
@@ -80,20 +157,32 @@ fn enter_edge(
     // remove e from tree
     // consider all edges going from head to tail component.
     // choose edge with minimum slack.
-    let (mut u, mut v) = graph
-        .edge_endpoints(edge)
-        .map(|(t, h)| (graph[t], graph[h]))
-        .unwrap();
-    let is_root_in_head = u.lim < v.lim;
+    let (mut u, mut v) = graph.edge_endpoints(edge).unwrap();
+    let is_root_in_head = graph[u].lim < graph[v].lim;
     if !is_root_in_head {
         std::mem::swap(&mut u, &mut v);
     }
 
-    graph
-        .edge_indices()
-        .filter(|e| !graph[*e].is_tree_edge && is_head_to_tail(graph, *e, u, is_root_in_head))
-        .min_by(|e1, e2| slack(graph, *e1, minimum_length).cmp(&slack(graph, *e2, minimum_length)))
-        .unwrap()
+    // only the edges of the subtree below the removed edge need to be searched
+    let mut entering: Option<(EdgeIndex, i32)> = None;
+    let direction = if is_root_in_head { Incoming } else { Outgoing };
+    for w in subtree(graph, u) {
+        for e in graph.edges_directed(w, direction) {
+            if e.weight().is_tree_edge || !is_head_to_tail(graph, e.id(), graph[u], is_root_in_head)
+            {
+                continue;
+            }
+            let slack = slack(graph, e.id(), minimum_length);
+            if entering.is_none_or(|(_, s)| slack < s) {
+                entering = Some((e.id(), slack));
+            }
+        }
+        // can't get any better than a tight edge
+        if let Some((e, 0)) = entering {
+            return e;
+        }
+    }
+    entering.unwrap().0
 }
 
 fn exchange(
@@ -102,6 +191,16 @@ fn exchange(
     swap_edge: EdgeIndex,
     minimum_length: i32,
 ) {
+    // move the subtree below the removed edge, so the new edge becomes tight.
+    // this needs to happen before low and lim values are updated
+    let (tail, head) = graph.edge_endpoints(removed_edge).unwrap();
+    let delta = slack(graph, swap_edge, minimum_length);
+    if graph[tail].lim < graph[head].lim {
+        update_subtree_ranks(graph, tail, -delta);
+    } else {
+        update_subtree_ranks(graph, head, delta);
+    }
+
     // swap edges
     graph[removed_edge].is_tree_edge = false;
     graph[swap_edge].is_tree_edge = true;
@@ -109,7 +208,6 @@ fn exchange(
     // update the graph
     let least_common_ancestor = update_cutvalues(graph, removed_edge, swap_edge);
     update_low_lim(graph, least_common_ancestor);
-    update_ranks(graph, minimum_length);
 }
 
 fn normalize(graph: &mut StableDiGraph<Vertex, Edge>) {
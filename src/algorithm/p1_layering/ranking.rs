@@ -1,12 +1,14 @@
-use std::collections::{HashSet, VecDeque};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 
 use log::{debug, info, trace};
 use petgraph::{
     stable_graph::{EdgeIndex, NodeIndex, StableDiGraph},
-    Direction::{self, Incoming, Outgoing},
+    visit::EdgeRef,
+    Direction::{Incoming, Outgoing},
 };
 
-use super::{cut_values::init_cutvalues, low_lim::init_low_lim, slack, Edge, Vertex};
+use super::{cut_values::init_cutvalues, low_lim::init_low_lim, Edge, Vertex};
 
 #[allow(dead_code)]
 pub(crate) fn print_ranks(graph: &StableDiGraph<Vertex, Edge>) {
@@ -27,18 +29,30 @@ pub(super) fn feasible_tree(graph: &mut StableDiGraph<Vertex, Edge>, minimum_len
     trace!(target: "ranking", "root of tree is: {}", tree_root.index());
 
     info!(target: "ranking", "Trying to build tight tree.");
-    while tight_tree(graph, tree_root, &mut HashSet::new(), minimum_length) < graph.node_count() {
+    let mut frontier = Frontier::default();
+    let mut tree_size = tight_tree(graph, tree_root, &mut frontier, minimum_length);
+    while tree_size < graph.node_count() {
         debug!(target: "ranking", "unable to build tight tree yet, finding edge which is not tight");
-        let edge = find_non_tight_edge(graph, minimum_length);
+        let (edge, slack) = frontier.pop(graph);
         let (tail, head) = graph.edge_endpoints(edge).unwrap();
         debug!(target: "ranking", "found edge: ({}, {})", tail.index(), head.index());
-        let mut delta = slack(graph, edge, minimum_length);
 
-        if graph[head].is_tree_vertex {
-            delta = -delta;
-        }
-
-        tighten_edge(graph, delta);
+        // move the whole tree, so the edge becomes tight
+        trace!(target: "ranking", "tighten all other tree edges by adjusting ranks by: {}", slack);
+        let new_vertex = if graph[head].is_tree_vertex {
+            frontier.offset -= slack;
+            tail
+        } else {
+            frontier.offset += slack;
+            head
+        };
+
+        graph[edge].is_tree_edge = true;
+        tree_size += tight_tree(graph, new_vertex, &mut frontier, minimum_length);
+    }
+    // every vertex is part of the tree now
+    for v in graph.node_indices().collect::<Vec<_>>() {
+        graph[v].rank += frontier.offset;
     }
 
     init_cutvalues(graph);
@@ -76,65 +90,316 @@ pub(super) fn move_vertices_down(graph: &mut StableDiGraph<Vertex, Edge>, minimu
     }
 }
 
-pub(super) fn update_ranks(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
-    info!(target: "ranking", "Updating node ranks");
-    let node = graph.node_indices().next().unwrap();
-    let mut visited = HashSet::from([node]);
-    graph[node].rank = 0;
-    let mut queue = VecDeque::from([node]);
-
-    while let Some(parent) = queue.pop_front() {
-        update_neighbor_ranks(
-            graph,
-            parent,
-            Outgoing,
-            1,
-            &mut queue,
-            &mut visited,
-            minimum_length,
-        );
-        update_neighbor_ranks(
-            graph,
-            parent,
-            Incoming,
-            -1,
-            &mut queue,
-            &mut visited,
-            minimum_length,
-        );
+/// Assigns ranks with the algorithm of Coffman and Graham, so that each layer
+/// contains at most `max_width` vertices.
+///
+/// First each vertex is labeled, preferring vertices whose predecessors have
+/// small labels. Then layers are filled from the bottom, picking the vertex
+/// with the largest label whose successors are all placed in lower layers.
+pub(super) fn coffman_graham(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    minimum_length: i32,
+    max_width: usize,
+) {
+    info!(target: "ranking", "Assigning ranks via Coffman-Graham, maximum width: {max_width}");
+    let max_width = max_width.max(1);
+
+    // label the vertices, a vertex is ready once all its predecessors are labeled
+    let mut label = HashMap::new();
+    let mut unlabeled = graph
+        .node_indices()
+        .map(|v| (v, graph.neighbors_directed(v, Incoming).count()))
+        .collect::<HashMap<_, _>>();
+    let mut ready = graph
+        .node_indices()
+        .filter(|v| unlabeled[v] == 0)
+        .map(|v| Reverse((Vec::new(), v)))
+        .collect::<BinaryHeap<_>>();
+    while let Some(Reverse((_, v))) = ready.pop() {
+        label.insert(v, label.len());
+        for n in graph.neighbors_directed(v, Outgoing) {
+            let count = unlabeled.get_mut(&n).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                let mut predecessors = graph
+                    .neighbors_directed(n, Incoming)
+                    .map(|p| label[&p])
+                    .collect::<Vec<_>>();
+                predecessors.sort_by(|a, b| b.cmp(a));
+                ready.push(Reverse((predecessors, n)));
+            }
+        }
+    }
+
+    // fill the layers from the bottom
+    let mut layer = HashMap::new();
+    let mut unplaced = graph
+        .node_indices()
+        .map(|v| (v, graph.neighbors_directed(v, Outgoing).count()))
+        .collect::<HashMap<_, _>>();
+    let mut ready = graph
+        .node_indices()
+        .filter(|v| unplaced[v] == 0)
+        .map(|v| (label[&v], v))
+        .collect::<BinaryHeap<_>>();
+    let (mut current, mut width) = (0, 0);
+    while let Some((_, v)) = ready.pop() {
+        let successor_in_layer = graph
+            .neighbors_directed(v, Outgoing)
+            .any(|s| layer[&s] == current);
+        if width == max_width || successor_in_layer {
+            current += 1;
+            width = 0;
+        }
+        layer.insert(v, current);
+        width += 1;
+        for n in graph.neighbors_directed(v, Incoming) {
+            let count = unplaced.get_mut(&n).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                ready.push((label[&n], n));
+            }
+        }
+    }
+
+    for v in graph.node_indices().collect::<Vec<_>>() {
+        graph[v].rank = (current - layer[&v]) * minimum_length;
+        trace!(target: "ranking", "Vertex: {}, rank: {}", v.index(), graph[v].rank);
     }
 }
 
-/// Builds a tight tree via depth first search
-/// Returns the number of verticees contained in the tree
+/// Reduces the number of dummy vertices with the node promotion heuristic of
+/// Nikolov and Tarassov, intended to be run after [move_vertices_up].
+///
+/// A vertex is moved down by one rank, if this shortens its outgoing edges
+/// more than it lengthens its incoming ones. Successors which would end up
+/// too close are moved down as well. Vertices are never moved below the
+/// lowest rank, so the height of the graph doesn't increase.
+pub(super) fn promote_vertices(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
+    info!(target: "ranking", "Promoting vertices to reduce the number of dummy vertices");
+    let Some(max_rank) = graph.node_weights().map(|w| w.rank).max() else {
+        return;
+    };
+
+    let mut rounds = 0;
+    loop {
+        let mut promotions = 0;
+        for v in graph.node_indices().collect::<Vec<_>>() {
+            if graph.edges_directed(v, Outgoing).next().is_none() {
+                continue;
+            }
+            let mut changed = Vec::new();
+            match promote_vertex(graph, v, minimum_length, max_rank, &mut changed) {
+                Some(dummy_diff) if dummy_diff < 0 => promotions += 1,
+                // undo the promotion
+                _ => {
+                    for (v, rank) in changed {
+                        graph[v].rank = rank;
+                    }
+                }
+            }
+        }
+        rounds += 1;
+        debug!(target: "ranking", "Promoted {promotions} vertices in round {rounds}");
+        if promotions == 0 {
+            break;
+        }
+    }
+}
+
+/// Moves `v` down by one rank and returns the change in the number of dummy
+/// vertices, or `None` if a vertex would be moved below `max_rank`.
+/// The previous ranks of all moved vertices are pushed to `changed`.
+fn promote_vertex(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    v: NodeIndex,
+    minimum_length: i32,
+    max_rank: i32,
+    changed: &mut Vec<(NodeIndex, i32)>,
+) -> Option<i32> {
+    if graph[v].rank >= max_rank {
+        return None;
+    }
+    let mut dummy_diff = 0;
+    let successors = graph.neighbors_directed(v, Outgoing).collect::<Vec<_>>();
+    for w in successors {
+        // successors may be visited multiple times if there are multiple edges
+        if graph[w].rank - graph[v].rank == minimum_length {
+            dummy_diff += promote_vertex(graph, w, minimum_length, max_rank, changed)?;
+        }
+    }
+    changed.push((v, graph[v].rank));
+    graph[v].rank += 1;
+    let incoming = graph.edges_directed(v, Incoming).count() as i32;
+    let outgoing = graph.edges_directed(v, Outgoing).count() as i32;
+    Some(dummy_diff + incoming - outgoing)
+}
+
+/// Balance step described in the paper by Gansner et al.
+///
+/// Vertices with equal in- and out-weight can be placed on any feasible rank
+/// between their neighbors without changing the total edge length. Each of
+/// them is moved to the feasible rank with the fewest vertices. Ranks are
+/// kept in steps of `minimum_length`, so layers don't get split up.
+pub(super) fn balance_ranks(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
+    info!(target: "ranking", "Balancing the number of vertices in each rank");
+    let Some(max_rank) = graph.node_weights().map(|w| w.rank).max() else {
+        return;
+    };
+    let mut rank_sizes = HashMap::<i32, usize>::new();
+    for w in graph.node_weights() {
+        *rank_sizes.entry(w.rank).or_default() += 1;
+    }
+
+    for v in graph.node_indices().collect::<Vec<_>>() {
+        let rank = graph[v].rank;
+        let (mut in_weight, mut lowest) = (0, 0);
+        for edge in graph.edges_directed(v, Incoming) {
+            in_weight += edge.weight().weight;
+            lowest = lowest.max(graph[edge.source()].rank + minimum_length);
+        }
+        let (mut out_weight, mut highest) = (0, max_rank);
+        for edge in graph.edges_directed(v, Outgoing) {
+            out_weight += edge.weight().weight;
+            highest = highest.min(graph[edge.target()].rank - minimum_length);
+        }
+        if in_weight != out_weight {
+            continue;
+        }
+
+        let candidates = (lowest..=highest).filter(|r| (r - rank) % minimum_length == 0);
+        let Some(best) = candidates.min_by_key(|r| rank_sizes.get(r).copied().unwrap_or(0)) else {
+            continue;
+        };
+        // only move if the vertex doesn't end up in an equally crowded rank
+        if rank_sizes.get(&best).copied().unwrap_or(0) + 1 < rank_sizes[&rank] {
+            trace!(target: "ranking", "Moving vertex {} from rank {rank} to {best}", v.index());
+            *rank_sizes.get_mut(&rank).unwrap() -= 1;
+            *rank_sizes.entry(best).or_default() += 1;
+            graph[v].rank = best;
+        }
+    }
+}
+
+/// Moves all vertices in the subtree below `root` by `delta` ranks.
+pub(super) fn update_subtree_ranks(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    root: NodeIndex,
+    delta: i32,
+) {
+    info!(target: "ranking", "Updating ranks of subtree below {} by {delta}", root.index());
+    for v in subtree(graph, root) {
+        graph[v].rank += delta;
+    }
+}
+
+/// Returns the vertices in the subtree below `root`, including `root`.
+///
+/// The children of a vertex are its neighbors in the tree with a lower lim value.
+pub(super) fn subtree(graph: &StableDiGraph<Vertex, Edge>, root: NodeIndex) -> Vec<NodeIndex> {
+    let mut vertices = vec![root];
+    let mut i = 0;
+    while let Some(&v) = vertices.get(i) {
+        let children = graph
+            .edges_directed(v, Outgoing)
+            .map(|e| (e, e.target()))
+            .chain(graph.edges_directed(v, Incoming).map(|e| (e, e.source())))
+            .filter(|(e, n)| e.weight().is_tree_edge && graph[*n].lim < graph[v].lim)
+            .map(|(_, n)| n);
+        vertices.extend(children);
+        i += 1;
+    }
+    vertices
+}
+
+/// Edges which connect the tight tree with the vertices outside of it,
+/// ordered by their slack.
+///
+/// Moving the tree only changes `offset`, the ranks of the tree vertices are
+/// stored relative to it. Since the vertices outside of the tree keep their
+/// ranks, the slack of an edge leaving the tree downwards is its key minus
+/// `offset`, the one of an edge leaving it upwards its key plus `offset`.
+/// Edges whose ends both joined the tree are removed when they are popped.
+#[derive(Default)]
+struct Frontier {
+    offset: i32,
+    down: BinaryHeap<Reverse<(i32, EdgeIndex)>>,
+    up: BinaryHeap<Reverse<(i32, EdgeIndex)>>,
+}
+
+impl Frontier {
+    /// Returns the edge with the minimum slack and its slack, preferring the
+    /// edge with the lower index.
+    fn pop(&mut self, graph: &StableDiGraph<Vertex, Edge>) -> (EdgeIndex, i32) {
+        let is_inside = |e: EdgeIndex| {
+            let (tail, head) = graph.edge_endpoints(e).unwrap();
+            graph[tail].is_tree_vertex && graph[head].is_tree_vertex
+        };
+        for heap in [&mut self.down, &mut self.up] {
+            while heap.peek().is_some_and(|Reverse((_, e))| is_inside(*e)) {
+                heap.pop();
+            }
+        }
+        let down = self
+            .down
+            .peek()
+            .map(|Reverse((k, e))| (k - self.offset, *e));
+        let up = self.up.peek().map(|Reverse((k, e))| (k + self.offset, *e));
+        let (slack, edge) = match (down, up) {
+            (Some(down), Some(up)) if (up.0, up.1.index()) < (down.0, down.1.index()) => {
+                self.up.pop();
+                up
+            }
+            (Some(down), _) => {
+                self.down.pop();
+                down
+            }
+            (None, Some(up)) => {
+                self.up.pop();
+                up
+            }
+            (None, None) => panic!("graph is not connected"),
+        };
+        (edge, slack)
+    }
+}
+
+/// Adds `vertex` and the vertices which can be reached from it via tight
+/// edges to the tree, via depth first search. The other edges leaving the
+/// tree are added to `frontier`.
+/// Returns the number of vertices added to the tree.
 fn tight_tree(
     graph: &mut StableDiGraph<Vertex, Edge>,
     vertex: NodeIndex,
-    visited: &mut HashSet<EdgeIndex>,
+    frontier: &mut Frontier,
     minimum_length: i32,
 ) -> usize {
-    // start from topmost nodes.
-    // then for each topmost node add nodes to tree until done. Then continue with next node until no more nodes are found.
     trace!(target: "ranking", "vertex: {}", vertex.index());
     let mut node_count = 1;
-    if !graph[vertex].is_tree_vertex {
-        graph[vertex].is_tree_vertex = true;
-    }
+    graph[vertex].is_tree_vertex = true;
+    graph[vertex].rank -= frontier.offset;
 
     let mut neighbors = graph.neighbors_undirected(vertex).detach();
     while let Some(edge) = neighbors.next_edge(graph) {
         let (tail, head) = graph.edge_endpoints(edge).unwrap();
         let other = if tail == vertex { head } else { tail };
-
-        if !visited.contains(&edge) {
-            visited.insert(edge);
-            if graph[edge].is_tree_edge {
-                node_count += tight_tree(graph, other, visited, minimum_length);
-            } else if slack(graph, edge, minimum_length) == 0 && !graph[other].is_tree_vertex {
-                trace!(target: "ranking", "adding edge with minimum slack: {}", edge.index());
-                graph[edge].is_tree_edge = true;
-                node_count += tight_tree(graph, other, visited, minimum_length);
-            }
+        if graph[other].is_tree_vertex {
+            continue;
+        }
+        let key = graph[head].rank - graph[tail].rank - minimum_length.max(graph[edge].min_length);
+        let slack = if tail == vertex {
+            key - frontier.offset
+        } else {
+            key + frontier.offset
+        };
+        if slack == 0 {
+            trace!(target: "ranking", "adding edge with minimum slack: {}", edge.index());
+            graph[edge].is_tree_edge = true;
+            node_count += tight_tree(graph, other, frontier, minimum_length);
+        } else if tail == vertex {
+            frontier.down.push(Reverse((key, edge)));
+        } else {
+            frontier.up.push(Reverse((key, edge)));
         }
     }
 
@@ -149,8 +414,8 @@ pub(crate) fn init_rank(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length:
     info!(target: "ranking", "Initializing ranks via topological sort.");
     for v in petgraph::algo::toposort(&*graph, None).unwrap() {
         let rank = graph
-            .neighbors_directed(v, Incoming)
-            .map(|n| graph[n].rank + minimum_length)
+            .edges_directed(v, Incoming)
+            .map(|e| graph[e.source()].rank + minimum_length.max(e.weight().min_length))
             .max();
 
         if let Some(rank) = rank {
@@ -160,58 +425,16 @@ pub(crate) fn init_rank(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length:
     }
 }
 
-fn is_incident_edge(graph: &StableDiGraph<Vertex, Edge>, edge: &EdgeIndex) -> bool {
-    let (tail, head) = graph.edge_endpoints(*edge).unwrap();
-    graph[tail].is_tree_vertex ^ graph[head].is_tree_vertex
-}
-
-fn find_non_tight_edge(graph: &StableDiGraph<Vertex, Edge>, minimum_length: i32) -> EdgeIndex {
-    graph
-        .edge_indices()
-        .filter(|e| !graph[*e].is_tree_edge && is_incident_edge(graph, e))
-        .min_by(|e1, e2| slack(graph, *e1, minimum_length).cmp(&slack(graph, *e2, minimum_length)))
-        .unwrap()
-}
-
-fn tighten_edge(graph: &mut StableDiGraph<Vertex, Edge>, delta: i32) {
-    trace!(target: "ranking", "tighten all other tree edges by adjusting ranks by: {}", delta);
-    for v in graph.node_indices().collect::<Vec<_>>() {
-        if graph[v].is_tree_vertex {
-            graph[v].rank += delta;
-        }
-    }
-}
-
-fn update_neighbor_ranks(
-    graph: &mut StableDiGraph<Vertex, Edge>,
-    parent: NodeIndex,
-    direction: Direction,
-    coefficient: i32,
-    queue: &mut VecDeque<NodeIndex>,
-    visited: &mut HashSet<NodeIndex>,
-    minimum_length: i32,
-) {
-    let mut walker = graph.neighbors_directed(parent, direction).detach();
-    while let Some((edge, other)) = walker.next(graph) {
-        if !graph[edge].is_tree_edge || visited.contains(&other) {
-            continue;
-        }
-        graph[other].rank = graph[parent].rank + minimum_length * coefficient;
-        trace!(target: "ranking", "updating ranks of {}, new rank is: {}", other.index(), graph[other].rank);
-        queue.push_back(other);
-        visited.insert(other);
-    }
-}
-
 #[cfg(test)]
 mod tests {
 
-    use std::collections::HashSet;
-
-    use petgraph::Direction::{Incoming, Outgoing};
+    use petgraph::{
+        stable_graph::NodeIndex,
+        Direction::{Incoming, Outgoing},
+    };
 
     use crate::algorithm::p1_layering::{
-        ranking::{feasible_tree, tight_tree},
+        ranking::{feasible_tree, tight_tree, Frontier},
         slack,
         tests::{
             EXAMPLE_GRAPH_FEASIBLE_TREE_POS_CUT_VALUE, EXAMPLE_GRAPH_NON_TIGHT_INITIAL_RANKING,
@@ -220,7 +443,8 @@ mod tests {
 
     use super::{
         super::tests::{GraphBuilder, EXAMPLE_GRAPH},
-        init_rank, update_ranks,
+        balance_ranks, coffman_graham, init_low_lim, init_rank, move_vertices_up, promote_vertices,
+        update_subtree_ranks,
     };
 
     #[test]
@@ -261,7 +485,7 @@ mod tests {
         let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH).build();
         init_rank(&mut graph, 1);
         let number_of_nodes = graph.node_count();
-        tight_tree(&mut graph, 0.into(), &mut HashSet::new(), 1);
+        tight_tree(&mut graph, 0.into(), &mut Frontier::default(), 1);
 
         assert_eq!(
             graph
@@ -284,7 +508,7 @@ mod tests {
         let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH).build();
         let number_of_nodes = graph.node_count();
         init_rank(&mut graph, 1);
-        tight_tree(&mut graph, 4.into(), &mut HashSet::new(), 1);
+        tight_tree(&mut graph, 4.into(), &mut Frontier::default(), 1);
 
         assert_eq!(
             graph
@@ -371,7 +595,33 @@ mod tests {
     }
 
     #[test]
-    fn update_ranks_example_graph() {
+    fn feasible_tree_tightens_many_edges() {
+        // every source is ranked above the chain and joins the tree on its
+        // own, after the tree was moved
+        let mut edges = (0..50).map(|i| (i, i + 1)).collect::<Vec<_>>();
+        for i in 0..25 {
+            edges.push((51 + i, 50 - i));
+            edges.push((51 + i, 49 - i));
+        }
+        let (mut graph, minimum_length, ..) = GraphBuilder::new(&edges).build();
+        init_rank(&mut graph, minimum_length);
+
+        feasible_tree(&mut graph, minimum_length);
+
+        assert_eq!(
+            graph.edge_weights().filter(|e| e.is_tree_edge).count(),
+            graph.node_count() - 1
+        );
+        for edge in graph.edge_indices() {
+            assert!(slack(&graph, edge, minimum_length) >= 0);
+            if graph[edge].is_tree_edge {
+                assert_eq!(slack(&graph, edge, minimum_length), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn update_subtree_ranks_example_graph() {
         let (mut graph, minimum_length, ..) = GraphBuilder::new(&EXAMPLE_GRAPH)
             .with_tree_edges(&EXAMPLE_GRAPH_FEASIBLE_TREE_POS_CUT_VALUE)
             .with_ranks(&[
@@ -396,7 +646,8 @@ mod tests {
             (6, 2),
             (7, 4),
         ];
-        update_ranks(&mut graph, minimum_length);
+        init_low_lim(&mut graph);
+        update_subtree_ranks(&mut graph, 4.into(), -minimum_length);
 
         for id in graph.node_indices() {
             let rank = graph[id].rank;
@@ -404,4 +655,112 @@ mod tests {
             assert_eq!(expected[id], (id, rank));
         }
     }
+
+    #[test]
+    fn coffman_graham_bounds_width() {
+        let mut edges = (1..=10).map(|i| (0, i)).collect::<Vec<_>>();
+        edges.extend((1..=10).map(|i| (i, 11)));
+        let (mut graph, minimum_length, ..) = GraphBuilder::new(&edges).build();
+
+        coffman_graham(&mut graph, minimum_length, 3);
+
+        let mut widths = std::collections::HashMap::new();
+        for v in graph.node_weights() {
+            *widths.entry(v.rank).or_insert(0) += 1;
+        }
+        assert!(widths.values().all(|w| *w <= 3));
+        // 0 and 11 on their own layers, 10 vertices on at least 4 layers
+        assert_eq!(widths.len(), 6);
+        for e in graph.edge_indices() {
+            assert!(slack(&graph, e, minimum_length) >= 0);
+        }
+        assert_eq!(graph.node_weights().map(|v| v.rank).min(), Some(0));
+    }
+
+    #[test]
+    fn coffman_graham_respects_minimum_length() {
+        let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH).build();
+
+        coffman_graham(&mut graph, 2, 2);
+
+        for e in graph.edge_indices() {
+            assert!(slack(&graph, e, 2) >= 0);
+        }
+        let mut widths = std::collections::HashMap::new();
+        for v in graph.node_weights() {
+            assert_eq!(v.rank % 2, 0);
+            *widths.entry(v.rank).or_insert(0) += 1;
+        }
+        assert!(widths.values().all(|w| *w <= 2));
+    }
+
+    fn dummy_count(
+        graph: &petgraph::stable_graph::StableDiGraph<super::Vertex, super::Edge>,
+    ) -> i32 {
+        graph
+            .edge_indices()
+            .map(|e| {
+                let (tail, head) = graph.edge_endpoints(e).unwrap();
+                graph[head].rank - graph[tail].rank - 1
+            })
+            .sum()
+    }
+
+    #[test]
+    fn promote_vertices_reduces_dummies() {
+        for edges in [
+            &EXAMPLE_GRAPH[..],
+            &EXAMPLE_GRAPH_NON_TIGHT_INITIAL_RANKING[..],
+            &EXAMPLE_GRAPH_FEASIBLE_TREE_POS_CUT_VALUE[..],
+        ] {
+            for minimum_length in [1, 2] {
+                let (mut graph, ..) = GraphBuilder::new(edges).build();
+                init_rank(&mut graph, minimum_length);
+                move_vertices_up(&mut graph, minimum_length);
+                let height = graph.node_weights().map(|w| w.rank).max();
+                let before = dummy_count(&graph);
+
+                promote_vertices(&mut graph, minimum_length);
+
+                assert!(dummy_count(&graph) <= before);
+                assert_eq!(graph.node_weights().map(|w| w.rank).max(), height);
+                for e in graph.edge_indices() {
+                    assert!(slack(&graph, e, minimum_length) >= 0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn promote_vertices_example_graph() {
+        // 4 is moved down next to 3, moving 6 would push 3 below the lowest rank
+        let (mut graph, minimum_length, ..) =
+            GraphBuilder::new(&[(0, 1), (1, 2), (2, 3), (4, 3), (5, 6), (6, 3), (6, 2)]).build();
+        init_rank(&mut graph, minimum_length);
+        move_vertices_up(&mut graph, minimum_length);
+        assert_eq!(dummy_count(&graph), 3);
+
+        promote_vertices(&mut graph, minimum_length);
+        assert_eq!(graph[NodeIndex::from(4)].rank, 2);
+        assert_eq!(graph[NodeIndex::from(6)].rank, 1);
+        assert_eq!(dummy_count(&graph), 1);
+    }
+
+    #[test]
+    fn balance_ranks_moves_vertices_to_smaller_ranks() {
+        let (mut graph, minimum_length, ..) =
+            GraphBuilder::new(&[(0, 1), (1, 2), (2, 3), (0, 4), (4, 3), (0, 5), (5, 3)]).build();
+        for (v, rank) in [(0, 0), (1, 1), (2, 2), (3, 3), (4, 1), (5, 1)] {
+            graph[NodeIndex::from(v)].rank = rank;
+        }
+        let length = dummy_count(&graph);
+
+        balance_ranks(&mut graph, minimum_length);
+
+        // 1 can't be moved and moving 5 as well would crowd rank 2 instead
+        assert_eq!(graph[NodeIndex::from(1)].rank, 1);
+        assert_eq!(graph[NodeIndex::from(4)].rank, 2);
+        assert_eq!(graph[NodeIndex::from(5)].rank, 1);
+        assert_eq!(dummy_count(&graph), length);
+    }
 }
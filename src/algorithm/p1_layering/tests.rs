@@ -186,7 +186,7 @@ impl GraphBuilder {
     #[allow(dead_code)]
     pub(super) fn with_connecting_path(mut self, connecting_path: &[(u32, u32)]) -> Self {
         self.connecting_path = connecting_path
-            .into_iter()
+            .iter()
             .map(|(tail, head)| {
                 self.graph
                     .find_edge_undirected((*tail).into(), (*head).into())
@@ -241,7 +241,8 @@ fn leave_edge_has_negative_cut_value() {
     init_cutvalues(&mut graph);
     init_low_lim(&mut graph);
 
-    let leave_edge = leave_edge(&graph);
+    let edges = graph.edge_indices().collect::<Vec<_>>();
+    let leave_edge = leave_edge(&graph, &edges, &mut 0, 30);
     assert!(leave_edge.is_some());
     let (tail, head) = graph.edge_endpoints(leave_edge.unwrap()).unwrap();
     assert_eq!(tail, NodeIndex::from(6));
@@ -257,10 +258,27 @@ fn leave_edge_has_no_negative_cut_value() {
     init_cutvalues(&mut graph);
     init_low_lim(&mut graph);
 
-    let leave_edge = leave_edge(&graph);
+    let edges = graph.edge_indices().collect::<Vec<_>>();
+    let leave_edge = leave_edge(&graph, &edges, &mut 0, 30);
     assert!(leave_edge.is_none());
 }
 
+#[test]
+fn leave_edge_continues_search_after_last_candidate() {
+    let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH)
+        .with_tree_edges(&EXAMPLE_GRAPH_FEASIBLE_TREE_NEG_CUT_VALUE)
+        .build();
+
+    init_cutvalues(&mut graph);
+    init_low_lim(&mut graph);
+
+    let edges = graph.edge_indices().collect::<Vec<_>>();
+    let position = |e| edges.iter().position(|x| *x == e).unwrap();
+    let mut search_start = 0;
+    let leave_edge = leave_edge(&graph, &edges, &mut search_start, 1).unwrap();
+    assert_eq!(search_start, position(leave_edge) + 1);
+}
+
 #[test]
 fn test_is_head_to_tail_true_root_in_tail() {
     // u is always considered to be the tail of the edge to be swapped
@@ -327,7 +345,8 @@ fn enter_edge_find_edge() {
     init_cutvalues(&mut graph);
     init_low_lim(&mut graph);
 
-    let leave_edge = leave_edge(&graph).unwrap();
+    let edges = graph.edge_indices().collect::<Vec<_>>();
+    let leave_edge = leave_edge(&graph, &edges, &mut 0, 30).unwrap();
     let enter_edge = enter_edge(&mut graph, leave_edge, 1);
     let (tail, head) = graph.edge_endpoints(enter_edge).unwrap();
     assert!(tail == NodeIndex::from(0));
@@ -336,10 +355,15 @@ fn enter_edge_find_edge() {
 
 mod integration {
 
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
     use crate::configure::{Config, RankingType};
     use petgraph::stable_graph::StableDiGraph;
 
     use crate::algorithm::p1_layering::{rank, slack, Edge, Vertex};
+    use crate::algorithm::Control;
+    use crate::CancellationToken;
 
     use super::{GraphBuilder, EXAMPLE_GRAPH};
 
@@ -368,37 +392,119 @@ mod integration {
     #[test]
     fn run_algorithm_example_graph() {
         let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH).build();
-        rank(&mut graph, 1, RankingType::MinimizeEdgeLength);
+        rank(
+            &mut graph,
+            1,
+            RankingType::MinimizeEdgeLength,
+            false,
+            30,
+            usize::MAX,
+            &Control::default(),
+        );
         assert!(is_correct(graph, 1));
     }
 
+    #[test]
+    fn run_algorithm_stops_after_max_iterations() {
+        // the feasible tree has a negative cut value, but ranks stay valid
+        let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH).build();
+        rank(
+            &mut graph,
+            1,
+            RankingType::MinimizeEdgeLength,
+            false,
+            30,
+            0,
+            &Control::default(),
+        );
+        assert!(graph.edge_indices().all(|e| slack(&graph, e, 1) >= 0));
+        assert_eq!(graph.node_weights().map(|w| w.rank).min(), Some(0));
+    }
+
+    #[test]
+    fn run_algorithm_stops_if_cancelled() {
+        let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH).build();
+        let iterations = Arc::new(AtomicUsize::new(0));
+        let counter = iterations.clone();
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        let control = Control {
+            progress: Some(Arc::new(move |_| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            })),
+            cancellation: Some(cancellation),
+        };
+        rank(
+            &mut graph,
+            1,
+            RankingType::MinimizeEdgeLength,
+            false,
+            30,
+            usize::MAX,
+            &control,
+        );
+        assert_eq!(iterations.load(Ordering::Relaxed), 0);
+        assert!(graph.edge_indices().all(|e| slack(&graph, e, 1) >= 0));
+    }
+
     #[test]
     fn run_algorithm_tree_500_nodes_three_edges_per_node() {
         use graph_generator::GraphLayout;
-        let edges = GraphLayout::new_from_num_nodes(500, 3)
-            .build_edges()
-            .into_iter()
-            .map(|(t, h)| (t as u32, h as u32))
-            .collect::<Vec<_>>();
+        let edges = GraphLayout::new_from_num_nodes(500, 3).build_edges();
         let (mut graph, ..) = GraphBuilder::new(&edges).build();
-        rank(&mut graph, 1, RankingType::MinimizeEdgeLength);
+        rank(
+            &mut graph,
+            1,
+            RankingType::MinimizeEdgeLength,
+            false,
+            30,
+            usize::MAX,
+            &Control::default(),
+        );
         assert!(is_correct(graph, 1));
     }
 
     #[test]
     fn run_algorithm_random_graph_1000_nodes() {
         use graph_generator::RandomLayout;
-        let edges = RandomLayout::new(1000)
-            .build_edges()
-            .into_iter()
-            .map(|(t, h)| (t as u32, h as u32))
-            .collect::<Vec<_>>();
+        let edges = RandomLayout::new(1000).build_edges();
         println!("built random layout");
         let (mut graph, ..) = GraphBuilder::new(&edges).build();
-        rank(&mut graph, 1, RankingType::MinimizeEdgeLength);
+        rank(
+            &mut graph,
+            1,
+            RankingType::MinimizeEdgeLength,
+            false,
+            30,
+            usize::MAX,
+            &Control::default(),
+        );
         assert!(is_correct(graph, 1));
     }
 
+    #[test]
+    fn run_coffman_graham_random_graph_1000_nodes() {
+        use graph_generator::RandomLayout;
+        let edges = RandomLayout::new(1000).build_edges();
+        let (mut graph, ..) = GraphBuilder::new(&edges).build();
+        rank(
+            &mut graph,
+            1,
+            RankingType::CoffmanGraham { max_width: 10 },
+            false,
+            30,
+            usize::MAX,
+            &Control::default(),
+        );
+
+        assert!(graph.edge_indices().all(|e| slack(&graph, e, 1) >= 0));
+        let mut widths = std::collections::HashMap::new();
+        for v in graph.node_weights() {
+            *widths.entry(v.rank).or_insert(0) += 1;
+        }
+        assert!(widths.values().all(|w| *w <= 10));
+    }
+
     #[test]
     fn db_nmpi_hlrs() {
         let edges = [
@@ -431,9 +537,30 @@ mod integration {
         ];
 
         let (graph, ..) = GraphBuilder::new(&edges).build();
-        let mut cfg = Config::default();
-        cfg.ranking_type = RankingType::Up;
-        cfg.dummy_vertices = true;
-        crate::algorithm::start(graph, cfg);
+        let cfg = Config {
+            ranking_type: RankingType::Up,
+            dummy_vertices: true,
+            ..Default::default()
+        };
+        crate::algorithm::start(graph, cfg, &Control::default()).unwrap();
+    }
+
+    #[test]
+    fn two_cycles_become_parallel_edges() {
+        // reversing one edge of each 2-cycle creates parallel edges, only one
+        // of which can be part of the tree
+        let edges = [
+            (17, 14),
+            (14, 24),
+            (3, 24),
+            (19, 4),
+            (8, 4),
+            (4, 19),
+            (3, 8),
+            (24, 3),
+        ];
+
+        let (graph, ..) = GraphBuilder::new(&edges).build();
+        crate::algorithm::start(graph, Config::default(), &Control::default()).unwrap();
     }
 }
@@ -0,0 +1,187 @@
+use std::time::Instant;
+
+use log::{debug, info};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::visit::{EdgeRef, NodeIndexable};
+use petgraph::Direction::Incoming;
+
+use super::{is_expired, Order};
+use crate::algorithm::{Edge, Vertex};
+
+/// Maximum number of partial orders visited, before the search gives up.
+const SEARCH_LIMIT: usize = 1_000_000;
+
+/// Returns the maximum number of vertices in two adjacent layers.
+pub(super) fn max_layer_pair_size(order: &Order) -> usize {
+    if order.len() == 1 {
+        return order[0].len();
+    }
+    order
+        .windows(2)
+        .map(|w| w[0].len() + w[1].len())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Searches the order with the fewest crossings via branch and bound.
+///
+/// The vertices are placed one after another, layer by layer from left to
+/// right. Once a layer is complete, the crossings between it and the layer
+/// above are known exactly. A branch is cut as soon as the crossings of the
+/// placed vertices plus a lower bound for the vertices still left in the
+/// current layer reach the number of crossings of the best order found so far.
+///
+/// Returns the best order and its number of crossings, which is `best` if
+/// there is no better order, and whether the search finished, i.e. whether
+/// the returned order is optimal.
+pub(super) fn branch_and_bound(
+    graph: &StableDiGraph<Vertex, Edge>,
+    best: Order,
+    best_crossings: usize,
+    deadline: Option<Instant>,
+) -> (Order, usize, bool) {
+    info!(target: "crossing_reduction", "Searching order with minimal crossings, upper bound: {best_crossings}");
+    let mut search = Search {
+        graph,
+        layers: best._inner.clone(),
+        current: vec![Vec::new(); best.len()],
+        positions: vec![0; graph.node_bound()],
+        best: None,
+        best_crossings,
+        visited: 0,
+        deadline,
+        aborted: false,
+    };
+    search.place_layer(0, 0);
+    let optimal = !search.aborted;
+    debug!(target: "crossing_reduction", "Visited {} partial orders, optimal: {optimal}", search.visited);
+    match search.best {
+        Some(order) => (Order::new(order), search.best_crossings, optimal),
+        None => (best, best_crossings, optimal),
+    }
+}
+
+struct Search<'a> {
+    graph: &'a StableDiGraph<Vertex, Edge>,
+    /// the vertices of each layer
+    layers: Vec<Vec<NodeIndex>>,
+    /// the partial order which is currently built
+    current: Vec<Vec<NodeIndex>>,
+    /// position of the placed vertices, indexed by the vertex index
+    positions: Vec<usize>,
+    /// best order found by the search, if it is better than the initial one
+    best: Option<Vec<Vec<NodeIndex>>>,
+    best_crossings: usize,
+    visited: usize,
+    deadline: Option<Instant>,
+    aborted: bool,
+}
+
+impl Search<'_> {
+    fn place_layer(&mut self, rank: usize, crossings: usize) {
+        if rank == self.layers.len() {
+            if crossings < self.best_crossings {
+                debug!(target: "crossing_reduction", "Found order with {crossings} crossings");
+                self.best_crossings = crossings;
+                self.best = Some(self.current.clone());
+            }
+            return;
+        }
+        let layer = self.layers[rank].clone();
+        let matrix = self.crossing_matrix(&layer);
+        let lower_bound = (0..layer.len())
+            .flat_map(|i| (i + 1..layer.len()).map(move |j| (i, j)))
+            .map(|(i, j)| matrix[i][j].min(matrix[j][i]))
+            .sum();
+        let mut placed = vec![false; layer.len()];
+        self.place_vertex(rank, &layer, &matrix, &mut placed, crossings, lower_bound);
+    }
+
+    fn place_vertex(
+        &mut self,
+        rank: usize,
+        layer: &[NodeIndex],
+        matrix: &[Vec<usize>],
+        placed: &mut [bool],
+        crossings: usize,
+        lower_bound: usize,
+    ) {
+        self.visited += 1;
+        if self.visited > SEARCH_LIMIT
+            || (self.visited.is_multiple_of(1024) && is_expired(self.deadline))
+        {
+            self.aborted = true;
+        }
+        if self.aborted {
+            return;
+        }
+        let pos = self.current[rank].len();
+        if pos == layer.len() {
+            self.place_layer(rank + 1, crossings);
+            return;
+        }
+
+        // placing a vertex in front of all remaining ones fixes its crossings with them
+        let mut candidates = (0..layer.len())
+            .filter(|i| !placed[*i])
+            .map(|i| {
+                let (added, bound) = (0..layer.len()).filter(|j| *j != i && !placed[*j]).fold(
+                    (0, 0),
+                    |(added, bound), j| {
+                        (added + matrix[i][j], bound + matrix[i][j].min(matrix[j][i]))
+                    },
+                );
+                (added, bound, i)
+            })
+            .collect::<Vec<_>>();
+        candidates.sort();
+
+        for (added, bound, i) in candidates {
+            let crossings = crossings + added;
+            let lower_bound = lower_bound - bound;
+            if crossings + lower_bound >= self.best_crossings {
+                continue;
+            }
+            placed[i] = true;
+            self.positions[layer[i].index()] = pos;
+            self.current[rank].push(layer[i]);
+            self.place_vertex(rank, layer, matrix, placed, crossings, lower_bound);
+            self.current[rank].pop();
+            placed[i] = false;
+            if self.aborted {
+                return;
+            }
+        }
+    }
+
+    /// Entry `[i][j]` contains the weighted crossings between the edges of
+    /// `layer[i]` and `layer[j]` to the layer above, if `i` is left of `j`.
+    fn crossing_matrix(&self, layer: &[NodeIndex]) -> Vec<Vec<usize>> {
+        let adjacent = layer
+            .iter()
+            .map(|v| {
+                let mut positions = self
+                    .graph
+                    .edges_directed(*v, Incoming)
+                    .map(|e| {
+                        (
+                            self.positions[e.source().index()],
+                            e.weight().weight as usize,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                positions.sort();
+                positions
+            })
+            .collect::<Vec<_>>();
+        adjacent
+            .iter()
+            .map(|v| {
+                adjacent
+                    .iter()
+                    .map(|w| Order::calculate_cross_count_two_vertices(v, w))
+                    .collect()
+            })
+            .collect()
+    }
+}
@@ -1,23 +1,29 @@
+mod exact;
 #[cfg(test)]
 mod tests;
-use std::collections::{HashMap, HashSet};
+
+use std::collections::VecDeque;
 use std::fmt::Display;
 use std::ops::{Deref, DerefMut};
+use std::time::{Duration, Instant};
 
 use log::{debug, info, trace};
 use petgraph::algo::toposort;
 use petgraph::stable_graph::{NodeIndex, StableDiGraph};
-use petgraph::Direction::{Incoming, Outgoing};
+use petgraph::visit::{EdgeRef, NodeIndexable};
+use petgraph::Direction::{self, Incoming, Outgoing};
 
 use crate::configure::CrossingMinimization;
-use crate::util::{iterate, radix_sort, IterDir};
+use crate::util::{iterate, radix_sort, IterDir, Rng};
+use crate::Phase;
 
-use super::{slack, Edge, Vertex};
+use super::{Control, Edge, Vertex};
 
 #[derive(Clone)]
 struct Order {
     _inner: Vec<Vec<NodeIndex>>,
-    positions: HashMap<NodeIndex, usize>,
+    /// position of each vertex in its layer, indexed by the vertex index
+    positions: Vec<usize>,
 }
 
 impl Display for Order {
@@ -36,10 +42,11 @@ impl Display for Order {
 
 impl Order {
     fn new(layers: Vec<Vec<NodeIndex>>) -> Self {
-        let mut positions = HashMap::new();
+        let bound = layers.iter().flatten().map(|v| v.index() + 1).max();
+        let mut positions = vec![0; bound.unwrap_or(0)];
         for l in &layers {
             for (pos, v) in l.iter().enumerate() {
-                positions.insert(*v, pos);
+                positions[v.index()] = pos;
             }
         }
         Self {
@@ -54,48 +61,68 @@ impl Order {
 
     fn exchange(&mut self, a: usize, b: usize, r: usize) {
         // first update positions, then swap
-        *self.positions.get_mut(&self._inner[r][a]).unwrap() = b;
-        *self.positions.get_mut(&self._inner[r][b]).unwrap() = a;
+        self.positions[self._inner[r][a].index()] = b;
+        self.positions[self._inner[r][b].index()] = a;
         self._inner[r].swap(a, b);
     }
 
+    /// Weighted crossings between the edges of `v` and `w`, if `v` is left of `w`
+    /// and if `w` is left of `v`.
     fn cross_count_two_vertices(
         &self,
         v: NodeIndex,
         w: NodeIndex,
         graph: &StableDiGraph<Vertex, Edge>,
-    ) -> usize {
-        let mut crossings = 0;
+    ) -> (usize, usize) {
+        let (mut v_w_crossings, mut w_v_crossings) = (0, 0);
         for dir in [Incoming, Outgoing] {
-            let mut v_adjacent = graph
-                .neighbors_directed(v, dir)
-                .map(|n| *self.positions.get(&n).unwrap())
-                .collect::<Vec<_>>();
-            let mut w_adjacent = graph
-                .neighbors_directed(w, dir)
-                .map(|n| *self.positions.get(&n).unwrap())
-                .collect::<Vec<_>>();
-            v_adjacent.sort();
-            w_adjacent.sort();
-            crossings += Self::calculate_cross_count_two_vertices(&v_adjacent, &w_adjacent);
-        }
-        crossings
+            let v_adjacent = self.adjacent_positions(graph, v, dir);
+            let w_adjacent = self.adjacent_positions(graph, w, dir);
+            v_w_crossings += Self::calculate_cross_count_two_vertices(&v_adjacent, &w_adjacent);
+            w_v_crossings += Self::calculate_cross_count_two_vertices(&w_adjacent, &v_adjacent);
+        }
+        (v_w_crossings, w_v_crossings)
     }
 
-    fn calculate_cross_count_two_vertices(v_adjacent: &[usize], w_adjacent: &[usize]) -> usize {
+    /// Positions of the neighbors in direction `dir`, together with the
+    /// weight of the edge, sorted by position.
+    fn adjacent_positions(
+        &self,
+        graph: &StableDiGraph<Vertex, Edge>,
+        v: NodeIndex,
+        dir: Direction,
+    ) -> Vec<(usize, usize)> {
+        let mut adjacent = graph
+            .edges_directed(v, dir)
+            .map(|e| {
+                let n = if dir == Incoming {
+                    e.source()
+                } else {
+                    e.target()
+                };
+                (self.positions[n.index()], e.weight().weight as usize)
+            })
+            .collect::<Vec<_>>();
+        adjacent.sort();
+        adjacent
+    }
+
+    /// Two edges cross if the edge of `v` ends right of the edge of `w`. Each
+    /// crossing costs the product of the weights of both edges.
+    fn calculate_cross_count_two_vertices(
+        v_adjacent: &[(usize, usize)],
+        w_adjacent: &[(usize, usize)],
+    ) -> usize {
         let mut all_crossings = 0;
         let mut k = 0;
-        for i in v_adjacent {
-            let i = *i;
-            let mut crossings = k;
-            while k < w_adjacent.len() && w_adjacent[k] < i {
-                let j = w_adjacent[k];
-                if i > j {
-                    crossings += 1;
-                }
+        // weight of the edges of w ending left of the current edge of v
+        let mut weight_left = 0;
+        for (i, weight) in v_adjacent {
+            while k < w_adjacent.len() && w_adjacent[k].0 < *i {
+                weight_left += w_adjacent[k].1;
                 k += 1;
             }
-            all_crossings += crossings;
+            all_crossings += weight * weight_left;
         }
         all_crossings
     }
@@ -108,6 +135,7 @@ impl Order {
         cross_count
     }
 
+    /// Weighted number of crossings between the layers `rank` and `rank + 1`.
     fn bilayer_cross_count(&self, graph: &StableDiGraph<Vertex, Edge>, rank: usize) -> usize {
         // find initial edge order
         let north = &self[rank];
@@ -118,24 +146,34 @@ impl Order {
             len /= 10;
             key_length += 1;
         }
-        let edge_endpoint_positions = north
-            .iter()
-            .flat_map(|v| {
-                radix_sort(
-                    graph
-                        .neighbors_directed(*v, Outgoing)
-                        .filter(|n| graph[*v].rank.abs_diff(graph[*n].rank) == 1)
-                        .filter_map(|n| self.positions.get(&n))
-                        .copied()
-                        .collect(),
-                    key_length,
-                )
-            })
-            .collect::<Vec<_>>();
+        // parallel edges don't cross each other, so their weights are summed up
+        let mut weights = vec![0; south.len()];
+        let mut edge_endpoint_positions = Vec::new();
+        for v in north {
+            let mut positions = Vec::new();
+            for edge in graph.edges_directed(*v, Outgoing) {
+                let n = edge.target();
+                let weight = edge.weight().weight as usize;
+                if weight == 0 || graph[*v].rank.abs_diff(graph[n].rank) != 1 {
+                    continue;
+                }
+                let pos = self.positions[n.index()];
+                if weights[pos] == 0 {
+                    positions.push(pos);
+                }
+                weights[pos] += weight;
+            }
+            for pos in radix_sort(positions, key_length) {
+                edge_endpoint_positions.push((pos, weights[pos]));
+                weights[pos] = 0;
+            }
+        }
         Self::count_crossings(edge_endpoint_positions, south.len())
     }
 
-    fn count_crossings(endpoints: Vec<usize>, south_len: usize) -> usize {
+    /// Counts crossings with the accumulator tree of Barth, Juenger and Mutzel,
+    /// which stores the weight of the edges ending in each subtree.
+    fn count_crossings(endpoints: Vec<(usize, usize)>, south_len: usize) -> usize {
         // build the accumulator tree
         let mut c = 0;
         while 1 << c < south_len {
@@ -148,20 +186,20 @@ impl Order {
         let mut cross_count = 0;
 
         // traverse through the positions and adjust tree nodes
-        for pos in endpoints {
+        for (pos, weight) in endpoints {
             let mut index = pos + first_index;
-            tree[index] += 1;
+            tree[index] += weight;
             while index > 0 {
-                // traverse up the tree, incrementing the nodes of the tree
-                // each time we visit them.
+                // traverse up the tree, adding the weight to the nodes of the
+                // tree each time we visit them.
                 //
-                // When visiting a left node, add the value of the node on the right to
-                // the cross count;
+                // When visiting a left node, add the value of the node on the right,
+                // multiplied by the weight, to the cross count;
                 if index % 2 == 1 {
-                    cross_count += tree[index + 1]
+                    cross_count += weight * tree[index + 1]
                 }
                 index = (index - 1) / 2;
-                tree[index] += 1;
+                tree[index] += weight;
             }
         }
         cross_count
@@ -192,21 +230,32 @@ impl DerefMut for Order {
     }
 }
 
-pub(super) fn insert_dummy_vertices(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
-    // find all edges that have slack of greater than 0.
-    // and insert dummy vertices
-    info!(target: "crossing_reduction", "Inserting dummy vertices for edges spanning more than {minimum_length} ranks");
+/// Inserts a dummy vertex on each rank which an edge passes, so each edge
+/// connects adjacent ranks. Edges are longer than one rank if they have slack
+/// or the minimum length is larger than one.
+pub(super) fn insert_dummy_vertices(graph: &mut StableDiGraph<Vertex, Edge>) {
+    info!(target: "crossing_reduction", "Inserting dummy vertices for edges spanning more than one rank");
     for edge in graph.edge_indices().collect::<Vec<_>>() {
-        if slack(graph, edge, minimum_length) > 0 {
-            let (mut tail, head) = graph.edge_endpoints(edge).unwrap();
+        let (mut tail, head) = graph.edge_endpoints(edge).unwrap();
+        if graph[head].rank - graph[tail].rank > 1 {
             trace!(target: "crossing_reduction", 
                 "Inserting {} dummy vertices between: ({}, {})", 
                 graph[head].rank - graph[tail].rank - 1, 
                 tail.index(), 
                 head.index());
 
-            // we don't need to remember edges that where removed
-            graph.remove_edge(edge);
+            // we don't need to remember edges that where removed,
+            // but the edges of the chain keep its weight and direction
+            let Edge {
+                weight,
+                is_reversed,
+                ..
+            } = graph.remove_edge(edge).unwrap();
+            let chain_edge = Edge {
+                weight,
+                is_reversed,
+                ..Default::default()
+            };
             for rank in (graph[tail].rank + 1)..graph[head].rank {
                 // usize usize::MAX id as reserved value for a dummy vertex
                 let d = Vertex {
@@ -218,10 +267,10 @@ pub(super) fn insert_dummy_vertices(graph: &mut StableDiGraph<Vertex, Edge>, min
                 graph[new].root = new;
                 graph[new].sink = new;
                 graph[new].rank = rank;
-                graph.add_edge(tail, new, Edge::default());
+                graph.add_edge(tail, new, chain_edge);
                 tail = new;
             }
-            graph.add_edge(tail, head, Edge::default()); // add last dummy edge connecting to the head
+            graph.add_edge(tail, head, chain_edge); // add last dummy edge connecting to the head
         }
     }
 }
@@ -239,18 +288,27 @@ pub(super) fn remove_dummy_vertices(
     let vertices = toposort(&*graph, None).unwrap();
     for v in vertices {
         let mut edges = Vec::new();
-        for mut n in graph.neighbors_directed(v, Outgoing) {
+        for edge in graph.edges_directed(v, Outgoing) {
+            let mut n = edge.target();
             if graph[n].is_dummy {
                 while graph[n].is_dummy {
                     let dummy_neighbors = graph.neighbors_directed(n, Outgoing).collect::<Vec<_>>();
                     //assert_eq!(dummy_neighbors.len(), 1);
                     n = dummy_neighbors[0];
                 }
-                edges.push((v, n));
+                edges.push((v, n, *edge.weight()));
             }
         }
-        for (tail, head) in edges {
-            graph.add_edge(tail, head, Edge::default());
+        for (tail, head, weight) in edges {
+            graph.add_edge(
+                tail,
+                head,
+                Edge {
+                    cut_value: None,
+                    is_tree_edge: false,
+                    ..weight
+                },
+            );
         }
     }
     // remove from order
@@ -261,23 +319,141 @@ pub(super) fn remove_dummy_vertices(
 }
 
 // TODO: Maybe write store all upper neighbors on vertex directly
+/// Limits for the number of sweeps and transpose passes, the time spent on
+/// crossing reduction and the number of restarts from other initial orders.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct SweepOptions {
+    pub(super) max_sweeps: usize,
+    /// number of sweeps without improvement, after which the search stops
+    pub(super) patience: usize,
+    /// maximum number of passes of transpose after each sweep
+    pub(super) transpose_passes: usize,
+    /// shared by all restarts
+    pub(super) time_budget: Option<Duration>,
+    /// number of additional initial orders, see [ordering]
+    pub(super) restarts: usize,
+    pub(super) seed: u64,
+    /// if the order is searched exactly after the heuristic, see [exact::branch_and_bound]
+    pub(super) exact: bool,
+    /// maximum number of vertices in two adjacent layers for the exact search
+    pub(super) exact_threshold: usize,
+}
+
+/// Reduces crossings starting from the order of a depth first search.
+///
+/// With restarts, the sweeps are repeated from the order of a breadth first
+/// search, the input order and then from random orders, generated from the
+/// seed. The order with the fewest crossings is returned.
+///
+/// If the exact search is enabled and no two adjacent layers contain more
+/// vertices than the threshold, the best order is then improved until it is
+/// optimal. Also returns whether the order is known to be optimal.
+///
+/// The sweeps are reported to `control`. If the build is cancelled, the best
+/// order found so far is returned.
 pub(super) fn ordering(
     graph: &mut StableDiGraph<Vertex, Edge>,
     crossing_minimization: CrossingMinimization,
     transpose: bool,
-) -> Vec<Vec<NodeIndex>> {
-    let order = init_order(graph);
+    options: SweepOptions,
+    control: &Control,
+) -> (Vec<Vec<NodeIndex>>, bool) {
+    let deadline = options.time_budget.map(|budget| Instant::now() + budget);
     // move downwards for crossing reduction
     let cm_method = match crossing_minimization {
-        CrossingMinimization::Barycenter => self::barycenter,
-        CrossingMinimization::Median => self::median,
+        CrossingMinimization::Barycenter => LayerMethod::Heuristic(self::barycenter),
+        CrossingMinimization::Median => LayerMethod::Heuristic(self::median),
+        CrossingMinimization::Sifting => LayerMethod::Sifting,
     };
-    let order = reduce_crossings_bilayer_sweep(graph, order, cm_method, transpose);
-    order._inner
+    let order = init_order(graph);
+    let (mut best, mut best_crossings) = reduce_crossings_bilayer_sweep(
+        graph, order, cm_method, transpose, options, deadline, control,
+    );
+
+    let mut rng = Rng::new(options.seed);
+    for restart in 0..options.restarts {
+        if best_crossings == 0 || is_expired(deadline) || control.is_cancelled() {
+            break;
+        }
+        let order = match restart {
+            0 => bfs_order(graph),
+            1 => input_order(graph),
+            _ => random_order(graph, &mut rng),
+        };
+        let (order, crossings) = reduce_crossings_bilayer_sweep(
+            graph, order, cm_method, transpose, options, deadline, control,
+        );
+        debug!(target: "crossing_reduction", "Restart {restart} found order with {crossings} crossings");
+        if crossings < best_crossings {
+            best = order;
+            best_crossings = crossings;
+        }
+    }
+
+    let layer_pair_size = exact::max_layer_pair_size(&best);
+    let optimal = if best_crossings == 0 {
+        true
+    } else if !options.exact || control.is_cancelled() {
+        false
+    } else if layer_pair_size > options.exact_threshold {
+        info!(target: "crossing_reduction",
+            "Layers contain up to {layer_pair_size} vertices, which is above the threshold of {} for exact crossing minimization",
+            options.exact_threshold);
+        false
+    } else {
+        let optimal;
+        (best, best_crossings, optimal) =
+            exact::branch_and_bound(graph, best, best_crossings, deadline);
+        optimal
+    };
+
+    resolve_type_2_conflicts(graph, &mut best);
+    // straightening long edges may introduce other crossings
+    let optimal = optimal && best.crossings(graph) == best_crossings;
+    (best._inner, optimal)
+}
+
+/// Returns the weighted number of crossings of the given order, which has to
+/// contain the dummy vertices of long edges.
+pub(super) fn crossings(graph: &StableDiGraph<Vertex, Edge>, layers: &[Vec<NodeIndex>]) -> usize {
+    Order::new(layers.to_vec()).crossings(graph)
+}
+
+/// Removes crossings between inner segments, i.e. edges between two dummy
+/// vertices. These are called type 2 conflicts by Brandes and Koepf and
+/// prevent that both long edges are aligned vertically.
+///
+/// Going down layer by layer, the dummy vertices incident to an inner segment
+/// are sorted by the position of their upper neighbor. They only swap the
+/// positions they already occupy, all other vertices stay in place.
+fn resolve_type_2_conflicts(graph: &StableDiGraph<Vertex, Edge>, order: &mut Order) {
+    info!(target: "crossing_reduction", "Resolving crossings between inner segments");
+    for rank in 1..order.max_rank() {
+        let (slots, mut segments): (Vec<_>, Vec<_>) = order[rank]
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, v)| {
+                let upper = graph.neighbors_directed(*v, Incoming).next()?;
+                (graph[*v].is_dummy && graph[upper].is_dummy).then_some((pos, (upper, *v)))
+            })
+            .unzip();
+        segments.sort_by_key(|(upper, _)| order.positions[upper.index()]);
+        for (pos, (_, v)) in slots.into_iter().zip(segments) {
+            order.positions[v.index()] = pos;
+            order._inner[rank][pos] = v;
+        }
+    }
 }
 
-type CMMethod =
-    fn(&StableDiGraph<Vertex, Edge>, NodeIndex, bool, &HashMap<NodeIndex, usize>) -> f64;
+type CMMethod = fn(&StableDiGraph<Vertex, Edge>, NodeIndex, bool, &[usize]) -> f64;
+
+/// How the layers are reordered during a sweep.
+#[derive(Clone, Copy)]
+enum LayerMethod {
+    /// Sort each layer by the value the heuristic assigns to its vertices
+    Heuristic(CMMethod),
+    Sifting,
+}
 
 fn init_order(graph: &StableDiGraph<Vertex, Edge>) -> Order {
     info!(target: "crossing_reduction", 
@@ -287,10 +463,10 @@ fn init_order(graph: &StableDiGraph<Vertex, Edge>) -> Order {
         v: NodeIndex,
         order: &mut Vec<Vec<NodeIndex>>,
         graph: &StableDiGraph<Vertex, Edge>,
-        visited: &mut HashSet<NodeIndex>,
+        visited: &mut [bool],
     ) {
-        if !visited.contains(&v) {
-            visited.insert(v);
+        if !visited[v.index()] {
+            visited[v.index()] = true;
             order[graph[v].rank as usize].push(v);
             graph
                 .neighbors_directed(v, Outgoing)
@@ -298,13 +474,8 @@ fn init_order(graph: &StableDiGraph<Vertex, Edge>) -> Order {
         }
     }
 
-    let max_rank = graph
-        .node_weights()
-        .map(|v| v.rank as usize)
-        .max_by(|r1, r2| r1.cmp(r2))
-        .expect("Got invalid ranking");
-    let mut order = vec![Vec::new(); max_rank + 1];
-    let mut visited = HashSet::new();
+    let mut order = vec![Vec::new(); max_rank(graph) + 1];
+    let mut visited = vec![false; graph.node_bound()];
 
     // build initial order via dfs
     graph
@@ -314,24 +485,89 @@ fn init_order(graph: &StableDiGraph<Vertex, Edge>) -> Order {
     Order::new(order)
 }
 
+/// Initial order via breadth first search, starting from each vertex which
+/// wasn't visited yet.
+fn bfs_order(graph: &StableDiGraph<Vertex, Edge>) -> Order {
+    let mut order = vec![Vec::new(); max_rank(graph) + 1];
+    let mut visited = vec![false; graph.node_bound()];
+    for start in graph.node_indices() {
+        if visited[start.index()] {
+            continue;
+        }
+        visited[start.index()] = true;
+        let mut queue = VecDeque::from([start]);
+        while let Some(v) = queue.pop_front() {
+            order[graph[v].rank as usize].push(v);
+            for n in graph.neighbors_directed(v, Outgoing) {
+                if !visited[n.index()] {
+                    visited[n.index()] = true;
+                    queue.push_back(n);
+                }
+            }
+        }
+    }
+    Order::new(order)
+}
+
+/// Initial order in which the vertices were added to the graph.
+fn input_order(graph: &StableDiGraph<Vertex, Edge>) -> Order {
+    let mut order = vec![Vec::new(); max_rank(graph) + 1];
+    for v in graph.node_indices() {
+        order[graph[v].rank as usize].push(v);
+    }
+    Order::new(order)
+}
+
+fn random_order(graph: &StableDiGraph<Vertex, Edge>, rng: &mut Rng) -> Order {
+    let mut order = input_order(graph)._inner;
+    for layer in &mut order {
+        rng.shuffle(layer);
+    }
+    Order::new(order)
+}
+
+fn max_rank(graph: &StableDiGraph<Vertex, Edge>) -> usize {
+    graph
+        .node_weights()
+        .map(|v| v.rank as usize)
+        .max()
+        .expect("Got invalid ranking")
+}
+
 fn reduce_crossings_bilayer_sweep(
     graph: &StableDiGraph<Vertex, Edge>,
     mut order: Order,
-    cm_method: CMMethod,
+    cm_method: LayerMethod,
     transpose: bool,
-) -> Order {
-    info!(target: "crossing_reduction", "Reducing crossings via bilayer sweep");
+    limits: SweepOptions,
+    deadline: Option<Instant>,
+    control: &Control,
+) -> (Order, usize) {
+    info!(target: "crossing_reduction", "Reducing crossings via bilayer sweep, limits: {limits:?}");
     let mut best_crossings = order.crossings(graph);
     debug!(target: "crossing_reduction", "Initial number of crossings: {best_crossings}");
     let mut last_best = 0;
     let mut best = order.clone();
-    for i in 0.. {
-        order = order_layer(graph, i % 2 == 0, &order, cm_method);
+    for i in 0..limits.max_sweeps {
+        match cm_method {
+            LayerMethod::Heuristic(cm_method) => {
+                order_layer(graph, i % 2 == 0, &mut order, cm_method)
+            }
+            LayerMethod::Sifting => sift_layers(graph, i % 2 == 0, &mut order),
+        }
         if transpose {
-            self::transpose(graph, &mut order, i % 2 == 0);
+            self::transpose(
+                graph,
+                &mut order,
+                i % 2 == 0,
+                limits.transpose_passes,
+                deadline,
+                control,
+            );
         }
         let crossings = order.crossings(graph);
         trace!(target: "crossing_reduction", "Current number of crossings: {crossings}");
+        control.report(Phase::CrossingReduction, i + 1);
         if crossings < best_crossings {
             best_crossings = crossings;
             debug!(target: "crossing_reduction", "Lowest number of crossings so far: {best_crossings}");
@@ -340,150 +576,236 @@ fn reduce_crossings_bilayer_sweep(
         } else {
             last_best += 1;
         }
-        if last_best == 4 {
-            info!(target: "crossing_reduction", "Didn't improve after 4 sweeps, returning");
-            return best;
+        if last_best >= limits.patience {
+            info!(target: "crossing_reduction", "Didn't improve after {last_best} sweeps, returning");
+            break;
+        }
+        if is_expired(deadline) {
+            info!(target: "crossing_reduction", "Time budget ran out after {} sweeps, returning", i + 1);
+            break;
+        }
+        if control.is_cancelled() {
+            info!(target: "crossing_reduction", "Cancelled after {} sweeps, returning", i + 1);
+            break;
         }
     }
-    best
+    (best, best_crossings)
 }
 
-fn transpose(graph: &StableDiGraph<Vertex, Edge>, order: &mut Order, move_down: bool) {
+fn is_expired(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|d| Instant::now() >= d)
+}
+
+fn transpose(
+    graph: &StableDiGraph<Vertex, Edge>,
+    order: &mut Order,
+    move_down: bool,
+    max_passes: usize,
+    deadline: Option<Instant>,
+    control: &Control,
+) {
     trace!(target: "crossings_reduction", 
         "Using transpose, try to swap vertices in each layer manually to reduce cross count");
 
     let mut improved = true;
+    let mut passes = 0;
     let iter_dir = if move_down {
         IterDir::Forward
     } else {
         IterDir::Backward
     };
 
-    while improved {
+    while improved && passes < max_passes {
         improved = false;
         for r in iterate(iter_dir, order.max_rank()) {
+            if is_expired(deadline) || control.is_cancelled() {
+                return;
+            }
             trace!(target: "reduce_crossings", "Transpose vertices in rank {r}");
-            for i in 0..order._inner[r].len() - 1 {
+            for i in 0..order._inner[r].len().saturating_sub(1) {
                 let v = order._inner[r][i];
                 let w = order._inner[r][i + 1];
-                let v_w_crossing = order.cross_count_two_vertices(v, w, graph);
-                let w_v_crossing = order.cross_count_two_vertices(w, v, graph);
+                let (v_w_crossing, w_v_crossing) = order.cross_count_two_vertices(v, w, graph);
                 if v_w_crossing > w_v_crossing {
                     improved = true;
                     order.exchange(i, i + 1, r);
                 }
             }
         }
+        passes += 1;
         trace!(target: "reduce_crossings", "Did improve: {improved}");
     }
 }
 
+/// Reorders all layers but the first one in the direction of the sweep.
+/// Positions are updated in place, so each layer is ordered by the new
+/// positions of the layer before it.
 fn order_layer(
     graph: &StableDiGraph<Vertex, Edge>,
     move_down: bool,
-    cur_order: &Order,
+    order: &mut Order,
     cm_method: CMMethod,
-) -> Order {
-    let mut new_order = vec![Vec::new(); cur_order.max_rank()];
-    let mut positions = cur_order.positions.clone();
+) {
     let dir: Vec<usize> = if move_down {
-        new_order[0].clone_from(&cur_order._inner[0]);
-        (1..cur_order.max_rank()).collect()
+        (1..order.max_rank()).collect()
     } else {
-        new_order[cur_order.max_rank() - 1].clone_from(&cur_order._inner[cur_order.max_rank() - 1]);
-        (0..cur_order.max_rank() - 1).rev().collect()
+        (0..order.max_rank() - 1).rev().collect()
     };
 
     for rank in dir {
         trace!(target: "crossing_reduction", "Updating order of vertices in rank {rank}");
         trace!(target: "crossing_reduction", "Original order: {:?}",
-            cur_order[rank]
+            order[rank]
                 .iter()
                 .map(|v| v.index())
                 .collect::<Vec<_>>()
                 .as_slice()
         );
 
-        new_order[rank].clone_from(&cur_order[rank]);
-        let ordering = new_order[rank]
+        let mut ordering = order[rank]
             .iter()
-            .map(|n| (*n, cm_method(graph, *n, move_down, &positions)))
-            .collect::<HashMap<NodeIndex, f64>>();
-
-        new_order[rank].sort_by(|a, b| ordering.get(a).partial_cmp(&ordering.get(b)).unwrap());
+            .map(|n| (cm_method(graph, *n, move_down, &order.positions), *n))
+            .collect::<Vec<_>>();
+        ordering.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
 
-        new_order[rank].iter().enumerate().for_each(|(pos, v)| {
-            positions.insert(*v, pos);
-        });
+        for (pos, (_, v)) in ordering.into_iter().enumerate() {
+            order.positions[v.index()] = pos;
+            order._inner[rank][pos] = v;
+        }
         trace!(target: "crossing_reduction", "Updated order : {:?}",
-            new_order[rank]
+            order[rank]
                 .iter()
                 .map(|v| v.index())
                 .collect::<Vec<_>>()
                 .as_slice()
         );
     }
+}
+
+/// Sifts the vertices of every layer in the direction of the sweep.
+fn sift_layers(graph: &StableDiGraph<Vertex, Edge>, move_down: bool, order: &mut Order) {
+    let iter_dir = if move_down {
+        IterDir::Forward
+    } else {
+        IterDir::Backward
+    };
+    for rank in iterate(iter_dir, order.max_rank()) {
+        trace!(target: "crossing_reduction", "Sifting vertices in rank {rank}");
+        sift(graph, order, rank);
+    }
+}
 
-    Order::new(new_order)
+/// Moves each vertex of the layer to the front and then step by step to the
+/// end, keeping track of the change in crossings with both neighboring layers.
+/// Afterwards the vertex is placed at the position with the fewest crossings,
+/// so sifting never increases the number of crossings.
+fn sift(graph: &StableDiGraph<Vertex, Edge>, order: &mut Order, rank: usize) {
+    let vertices = order[rank].clone();
+    for v in vertices {
+        let start = order.positions[v.index()];
+        let mut pos = start;
+        // change in crossings compared to the start position
+        let mut delta = 0;
+        while pos > 0 {
+            let w = order[rank][pos - 1];
+            let (v_w_crossings, w_v_crossings) = order.cross_count_two_vertices(v, w, graph);
+            delta += v_w_crossings as isize - w_v_crossings as isize;
+            order.exchange(pos - 1, pos, rank);
+            pos -= 1;
+        }
+        let (mut best_delta, mut best_pos) = (0, start);
+        if delta < best_delta {
+            (best_delta, best_pos) = (delta, pos);
+        }
+        while pos + 1 < order[rank].len() {
+            let w = order[rank][pos + 1];
+            let (v_w_crossings, w_v_crossings) = order.cross_count_two_vertices(v, w, graph);
+            delta += w_v_crossings as isize - v_w_crossings as isize;
+            order.exchange(pos, pos + 1, rank);
+            pos += 1;
+            if delta < best_delta {
+                (best_delta, best_pos) = (delta, pos);
+            }
+        }
+        while pos > best_pos {
+            order.exchange(pos - 1, pos, rank);
+            pos -= 1;
+        }
+    }
 }
 
+/// Average of the positions of the neighbors, weighted by the weights of the edges.
 fn barycenter(
     graph: &StableDiGraph<Vertex, Edge>,
     vertex: NodeIndex,
     move_down: bool,
-    positions: &HashMap<NodeIndex, usize>,
+    positions: &[usize],
 ) -> f64 {
-    let neighbors: Vec<_> = if move_down {
-        graph.neighbors_directed(vertex, Incoming).collect()
-    } else {
-        graph.neighbors_directed(vertex, Outgoing).collect()
-    };
+    let dir = if move_down { Incoming } else { Outgoing };
+    let (sum, weights) = graph
+        .edges_directed(vertex, dir)
+        .map(|e| {
+            let n = if move_down { e.source() } else { e.target() };
+            (positions[n.index()], e.weight().weight as usize)
+        })
+        .fold((0, 0), |(sum, weights), (pos, weight)| {
+            (sum + pos * weight, weights + weight)
+        });
 
-    if neighbors.is_empty() {
-        return *positions.get(&vertex).unwrap() as f64;
+    if weights == 0 {
+        return positions[vertex.index()] as f64;
     }
-
-    // Only look at direct neighbors
-    let adjacent = neighbors
-        .into_iter()
-        // .filter(|n| graph[vertex].rank.abs_diff(graph[*n].rank) == 1)
-        .map(|n| *positions.get(&n).unwrap())
-        .collect::<Vec<usize>>();
-
-    let bary = adjacent.iter().sum::<usize>() as f64 / adjacent.len() as f64;
-    bary
+    sum as f64 / weights as f64
 }
 
+/// Weighted median of the positions of the neighbors. An edge with weight `w`
+/// counts as `w` edges ending at the same position.
 fn median(
     graph: &StableDiGraph<Vertex, Edge>,
     vertex: NodeIndex,
     move_down: bool,
-    positions: &HashMap<NodeIndex, usize>,
+    positions: &[usize],
 ) -> f64 {
-    let neighbors = if move_down {
-        graph.neighbors_directed(vertex, Incoming)
-    } else {
-        graph.neighbors_directed(vertex, Outgoing)
-    };
+    let dir = if move_down { Incoming } else { Outgoing };
     // Only look at direct neighbors
-    let mut adjacent = neighbors
-        .filter(|n| graph[vertex].rank.abs_diff(graph[*n].rank) == 1)
-        .map(|n| *positions.get(&n).unwrap())
+    let mut adjacent = graph
+        .edges_directed(vertex, dir)
+        .map(|e| {
+            let n = if move_down { e.source() } else { e.target() };
+            (n, e.weight().weight as usize)
+        })
+        .filter(|(n, weight)| *weight > 0 && graph[vertex].rank.abs_diff(graph[*n].rank) == 1)
+        .map(|(n, weight)| (positions[n.index()], weight))
         .collect::<Vec<_>>();
 
     adjacent.sort();
 
-    let length_p = adjacent.len();
+    let length_p = adjacent.iter().map(|(_, weight)| weight).sum::<usize>();
+    let at = |k: usize| nth_weighted(&adjacent, k);
     let m = length_p / 2;
     if length_p == 0 {
         f64::MAX
     } else if length_p % 2 == 1 {
-        adjacent[m] as f64
+        at(m) as f64
     } else if length_p == 2 {
-        (adjacent[0] + adjacent[1]) as f64 / 2.
+        (at(0) + at(1)) as f64 / 2.
     } else {
-        let left = adjacent[m - 1] - adjacent[0];
-        let right = adjacent[length_p - 1] - adjacent[m];
-        (adjacent[m - 1] * right + adjacent[m] * left) as f64 / (left + right) as f64
+        let left = at(m - 1) - at(0);
+        let right = at(length_p - 1) - at(m);
+        if left + right == 0 {
+            return at(m) as f64;
+        }
+        (at(m - 1) * right + at(m) * left) as f64 / (left + right) as f64
+    }
+}
+
+/// Position of the `k`th entry, if each position is repeated by its weight.
+fn nth_weighted(adjacent: &[(usize, usize)], mut k: usize) -> usize {
+    for (pos, weight) in adjacent {
+        if k < *weight {
+            return *pos;
+        }
+        k -= weight;
     }
+    unreachable!("k is smaller than the sum of weights")
 }
@@ -171,10 +171,10 @@ mod insert_dummy_vertices {
 
     #[test]
     fn insert_dummy_vertices_one_dummy() {
-        let (mut graph, minimum_length) =
+        let (mut graph, _) =
             GraphBuilder::new_from_edges_with_ranking(&ONE_DUMMY, &ONE_DUMMY_RANKS).build();
         let n_vertices = graph.node_count();
-        insert_dummy_vertices(&mut graph, minimum_length);
+        insert_dummy_vertices(&mut graph);
         // one dummy vertex
         assert_eq!(graph.node_weights().filter(|w| w.is_dummy).count(), 1);
         // one more vertex
@@ -183,10 +183,10 @@ mod insert_dummy_vertices {
 
     #[test]
     fn insert_dummy_vertices_three_dummies() {
-        let (mut graph, minimum_length) =
+        let (mut graph, _) =
             GraphBuilder::new_from_edges_with_ranking(&THREE_DUMMIES, &THREE_DUMMIES_RANKS).build();
         let n_vertices = graph.node_count();
-        insert_dummy_vertices(&mut graph, minimum_length);
+        insert_dummy_vertices(&mut graph);
         // one dummy vertex
         assert_eq!(graph.node_weights().filter(|w| w.is_dummy).count(), 3);
         // one more vertex
@@ -195,11 +195,11 @@ mod insert_dummy_vertices {
 
     #[test]
     fn insert_dummy_vertices_7_dummies() {
-        let (mut graph, minimum_length) =
+        let (mut graph, _) =
             GraphBuilder::new_from_edges_with_ranking(&COMPLEX_EXAMPLE, &COMPLEX_EXAMPLE_RANKS)
                 .build();
         let n_vertices = graph.node_count();
-        insert_dummy_vertices(&mut graph, minimum_length);
+        insert_dummy_vertices(&mut graph);
         // one dummy vertex
         assert_eq!(graph.node_weights().filter(|w| w.is_dummy).count(), 7);
         // one more vertex
@@ -258,9 +258,9 @@ mod insert_dummy_vertices {
             e.0 -= 1;
             e.1 -= 1;
         }
-        let g = StableDiGraph::from_edges(&edges);
+        let g = StableDiGraph::from_edges(edges);
         let c = Config::default();
-        crate::algorithm::start(g, c);
+        crate::algorithm::start(g, c, &crate::algorithm::Control::default()).unwrap();
     }
 }
 
@@ -274,9 +274,9 @@ mod init_order {
 
     #[test]
     fn all_neighbors_must_be_at_adjacent_level_one_dummy() {
-        let (mut graph, minimum_length) =
+        let (mut graph, _) =
             GraphBuilder::new_from_edges_with_ranking(&ONE_DUMMY, &ONE_DUMMY_RANKS).build();
-        insert_dummy_vertices(&mut graph, minimum_length);
+        insert_dummy_vertices(&mut graph);
         for v in graph.node_indices() {
             let rank = graph[v].rank;
             for n in graph.neighbors_undirected(v) {
@@ -287,9 +287,9 @@ mod init_order {
 
     #[test]
     fn all_neighbors_must_be_at_adjacent_level_three_dummies() {
-        let (mut graph, minimum_length) =
+        let (mut graph, _) =
             GraphBuilder::new_from_edges_with_ranking(&THREE_DUMMIES, &THREE_DUMMIES_RANKS).build();
-        insert_dummy_vertices(&mut graph, minimum_length);
+        insert_dummy_vertices(&mut graph);
         for v in graph.node_indices() {
             let rank = graph[v].rank;
             for n in graph.neighbors_undirected(v) {
@@ -300,11 +300,11 @@ mod init_order {
 
     #[test]
     fn all_neighbors_must_be_at_adjacent_level_seven_dummies() {
-        let (mut graph, minimum_length) =
+        let (mut graph, _) =
             GraphBuilder::new_from_edges_with_ranking(&COMPLEX_EXAMPLE, &COMPLEX_EXAMPLE_RANKS)
                 .build();
 
-        insert_dummy_vertices(&mut graph, minimum_length);
+        insert_dummy_vertices(&mut graph);
         for v in graph.node_indices() {
             let rank = graph[v].rank;
             for n in graph.neighbors_undirected(v) {
@@ -317,7 +317,11 @@ mod init_order {
 // TODO: Add new tests for Order crosscount
 #[cfg(test)]
 mod order {
-    use crate::algorithm::{p2::order_layer, p2_reduce_crossings::Order, Edge, Vertex};
+    use crate::algorithm::{
+        p2::{barycenter, median, order_layer, resolve_type_2_conflicts, sift},
+        p2_reduce_crossings::Order,
+        Edge, Vertex,
+    };
     use petgraph::stable_graph::StableDiGraph;
 
     #[test]
@@ -421,16 +425,345 @@ mod order {
             vec![n0, n2, n4, n3, n6, n7, n1, n5],
             vec![s0, s1, s2, s3, s4],
         ];
-        let order = Order::new(_inner);
-        let expected_order = order_layer(
+        let mut order = Order::new(_inner);
+        order_layer(
             &graph,
             false,
-            &order,
+            &mut order,
             crate::algorithm::p2_reduce_crossings::barycenter,
         );
+        assert_eq!(order._inner[0], vec![n0, n1, n2, n3, n4, n5, n6, n7]);
+    }
+
+    #[test]
+    fn weighted_crossings() {
+        let mut graph = StableDiGraph::new();
+        let n0 = graph.add_node(Vertex::new_with_rank(0));
+        let n1 = graph.add_node(Vertex::new_with_rank(0));
+        let n2 = graph.add_node(Vertex::new_with_rank(0));
+        let s0 = graph.add_node(Vertex::new_with_rank(1));
+        let s1 = graph.add_node(Vertex::new_with_rank(1));
+
+        let heavy = Edge {
+            weight: 3,
+            ..Default::default()
+        };
+        graph.add_edge(n0, s1, heavy);
+        graph.add_edge(n1, s0, Edge::default());
+        graph.add_edge(n2, s0, heavy);
+
+        let order = Order::new(vec![vec![n0, n1, n2], vec![s0, s1]]);
+        assert_eq!(order.bilayer_cross_count(&graph, 0), 3 + 9);
         assert_eq!(
-            expected_order._inner[0],
-            vec![n0, n1, n2, n3, n4, n5, n6, n7]
+            order.cross_count_two_vertices(n0, n1, &graph).0
+                + order.cross_count_two_vertices(n0, n2, &graph).0
+                + order.cross_count_two_vertices(n1, n2, &graph).0,
+            3 + 9
+        );
+    }
+
+    #[test]
+    fn weighted_heuristics() {
+        let mut graph = StableDiGraph::new();
+        let n = (0..4)
+            .map(|_| graph.add_node(Vertex::new_with_rank(0)))
+            .collect::<Vec<_>>();
+        let s0 = graph.add_node(Vertex::new_with_rank(1));
+        graph.add_edge(n[0], s0, Edge::default());
+        graph.add_edge(
+            n[3],
+            s0,
+            Edge {
+                weight: 2,
+                ..Default::default()
+            },
+        );
+        let order = Order::new(vec![n, vec![s0]]);
+        assert_eq!(barycenter(&graph, s0, true, &order.positions), 2.);
+        assert_eq!(median(&graph, s0, true, &order.positions), 3.);
+    }
+
+    #[test]
+    fn resolve_crossing_inner_segments() {
+        let mut graph = StableDiGraph::new();
+        let dummy = |rank| Vertex {
+            is_dummy: true,
+            ..Vertex::new_with_rank(rank)
+        };
+        let a0 = graph.add_node(dummy(0));
+        let b0 = graph.add_node(dummy(0));
+        let v = graph.add_node(Vertex::new_with_rank(1));
+        let a1 = graph.add_node(dummy(1));
+        let b1 = graph.add_node(dummy(1));
+        graph.add_edge(a0, a1, Edge::default());
+        graph.add_edge(b0, b1, Edge::default());
+        graph.add_edge(a0, v, Edge::default());
+
+        let mut order = Order::new(vec![vec![a0, b0], vec![b1, v, a1]]);
+        resolve_type_2_conflicts(&graph, &mut order);
+        assert_eq!(order._inner[1], vec![a1, v, b1]);
+        assert_eq!(order.positions[a1.index()], 0);
+        assert_eq!(order.positions[b1.index()], 2);
+    }
+
+    #[test]
+    fn sift_removes_all_crossings() {
+        let mut graph = StableDiGraph::new();
+        let n0 = graph.add_node(Vertex::new_with_rank(0));
+        let n1 = graph.add_node(Vertex::new_with_rank(0));
+        let n2 = graph.add_node(Vertex::new_with_rank(0));
+        let n3 = graph.add_node(Vertex::new_with_rank(0));
+        let s0 = graph.add_node(Vertex::new_with_rank(1));
+        let s1 = graph.add_node(Vertex::new_with_rank(1));
+        let s2 = graph.add_node(Vertex::new_with_rank(1));
+        let s3 = graph.add_node(Vertex::new_with_rank(1));
+
+        graph.add_edge(n0, s3, Edge::default());
+        graph.add_edge(n1, s2, Edge::default());
+        graph.add_edge(n2, s1, Edge::default());
+        graph.add_edge(n3, s0, Edge::default());
+
+        let mut order = Order::new(vec![vec![n0, n1, n2, n3], vec![s0, s1, s2, s3]]);
+        sift(&graph, &mut order, 1);
+        assert_eq!(order._inner[1], vec![s3, s2, s1, s0]);
+        assert_eq!(order.crossings(&graph), 0);
+    }
+}
+
+mod ordering {
+    use std::time::Duration;
+
+    use crate::algorithm::p2_reduce_crossings::{
+        init_order, insert_dummy_vertices, ordering, Order, SweepOptions,
+    };
+    use crate::algorithm::Control;
+    use crate::configure::CrossingMinimization;
+
+    use super::{GraphBuilder, COMPLEX_EXAMPLE, COMPLEX_EXAMPLE_RANKS};
+
+    pub(super) static UNLIMITED: SweepOptions = SweepOptions {
+        max_sweeps: usize::MAX,
+        patience: 4,
+        transpose_passes: usize::MAX,
+        time_budget: None,
+        restarts: 0,
+        seed: 0,
+        exact: false,
+        exact_threshold: 30,
+    };
+
+    #[test]
+    fn no_sweeps_keeps_initial_order() {
+        let (mut graph, _) =
+            GraphBuilder::new_from_edges_with_ranking(&COMPLEX_EXAMPLE, &COMPLEX_EXAMPLE_RANKS)
+                .build();
+        insert_dummy_vertices(&mut graph);
+        let initial = init_order(&graph);
+        let limits = SweepOptions {
+            max_sweeps: 0,
+            ..UNLIMITED
+        };
+        let order = ordering(
+            &mut graph,
+            CrossingMinimization::Barycenter,
+            true,
+            limits,
+            &Control::default(),
+        )
+        .0;
+        assert_eq!(order, initial._inner);
+    }
+
+    #[test]
+    fn exhausted_time_budget_returns_best_order() {
+        let (mut graph, _) =
+            GraphBuilder::new_from_edges_with_ranking(&COMPLEX_EXAMPLE, &COMPLEX_EXAMPLE_RANKS)
+                .build();
+        insert_dummy_vertices(&mut graph);
+        let initial = init_order(&graph).crossings(&graph);
+        let limits = SweepOptions {
+            time_budget: Some(Duration::ZERO),
+            ..UNLIMITED
+        };
+        let order = Order::new(
+            ordering(
+                &mut graph,
+                CrossingMinimization::Barycenter,
+                true,
+                limits,
+                &Control::default(),
+            )
+            .0,
+        );
+        assert_eq!(order.iter().flatten().count(), graph.node_count());
+        assert!(order.crossings(&graph) <= initial);
+    }
+
+    #[test]
+    fn restarts_dont_increase_crossings() {
+        let (mut graph, _) =
+            GraphBuilder::new_from_edges_with_ranking(&COMPLEX_EXAMPLE, &COMPLEX_EXAMPLE_RANKS)
+                .build();
+        insert_dummy_vertices(&mut graph);
+        let single = Order::new(
+            ordering(
+                &mut graph,
+                CrossingMinimization::Barycenter,
+                false,
+                UNLIMITED,
+                &Control::default(),
+            )
+            .0,
         );
+        let options = SweepOptions {
+            restarts: 6,
+            ..UNLIMITED
+        };
+        let restarted = Order::new(
+            ordering(
+                &mut graph,
+                CrossingMinimization::Barycenter,
+                false,
+                options,
+                &Control::default(),
+            )
+            .0,
+        );
+        assert!(restarted.crossings(&graph) <= single.crossings(&graph));
+    }
+
+    #[test]
+    fn same_seed_gives_same_order() {
+        let (mut graph, _) =
+            GraphBuilder::new_from_edges_with_ranking(&COMPLEX_EXAMPLE, &COMPLEX_EXAMPLE_RANKS)
+                .build();
+        insert_dummy_vertices(&mut graph);
+        let options = SweepOptions {
+            restarts: 6,
+            seed: 7,
+            ..UNLIMITED
+        };
+        let first = ordering(
+            &mut graph,
+            CrossingMinimization::Median,
+            false,
+            options,
+            &Control::default(),
+        );
+        let second = ordering(
+            &mut graph,
+            CrossingMinimization::Median,
+            false,
+            options,
+            &Control::default(),
+        );
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn sifting_doesnt_increase_crossings() {
+        let (mut graph, _) =
+            GraphBuilder::new_from_edges_with_ranking(&COMPLEX_EXAMPLE, &COMPLEX_EXAMPLE_RANKS)
+                .build();
+        insert_dummy_vertices(&mut graph);
+        let initial = init_order(&graph).crossings(&graph);
+        for transpose in [false, true] {
+            let order = Order::new(
+                ordering(
+                    &mut graph,
+                    CrossingMinimization::Sifting,
+                    transpose,
+                    UNLIMITED,
+                    &Control::default(),
+                )
+                .0,
+            );
+            assert_eq!(order.iter().flatten().count(), graph.node_count());
+            assert!(order.crossings(&graph) <= initial);
+        }
+    }
+}
+
+mod exact {
+    use petgraph::stable_graph::StableDiGraph;
+
+    use crate::algorithm::p2_reduce_crossings::{
+        exact::branch_and_bound, ordering, Order, SweepOptions,
+    };
+    use crate::algorithm::{Control, Edge, Vertex};
+    use crate::configure::CrossingMinimization;
+
+    use super::ordering::UNLIMITED;
+
+    /// Two complete bipartite layers, every order has 9 crossings.
+    fn k_3_3() -> (StableDiGraph<Vertex, Edge>, Order) {
+        let mut graph = StableDiGraph::new();
+        let north = (0..3)
+            .map(|_| graph.add_node(Vertex::new_with_rank(0)))
+            .collect::<Vec<_>>();
+        let south = (0..3)
+            .map(|_| graph.add_node(Vertex::new_with_rank(1)))
+            .collect::<Vec<_>>();
+        for n in &north {
+            for s in &south {
+                graph.add_edge(*n, *s, Edge::default());
+            }
+        }
+        (graph, Order::new(vec![north, south]))
+    }
+
+    #[test]
+    fn branch_and_bound_removes_all_crossings() {
+        let mut graph = StableDiGraph::new();
+        let n = (0..4)
+            .map(|_| graph.add_node(Vertex::new_with_rank(0)))
+            .collect::<Vec<_>>();
+        let s = (0..4)
+            .map(|_| graph.add_node(Vertex::new_with_rank(1)))
+            .collect::<Vec<_>>();
+        for i in 0..4 {
+            graph.add_edge(n[i], s[3 - i], Edge::default());
+        }
+        let order = Order::new(vec![n, s]);
+        let crossings = order.crossings(&graph);
+        assert_eq!(crossings, 6);
+
+        let (order, crossings, optimal) = branch_and_bound(&graph, order, crossings, None);
+        assert_eq!(crossings, 0);
+        assert_eq!(order.crossings(&graph), 0);
+        assert!(optimal);
+    }
+
+    #[test]
+    fn branch_and_bound_proves_initial_order_optimal() {
+        let (graph, order) = k_3_3();
+        let initial = order._inner.clone();
+        let (order, crossings, optimal) = branch_and_bound(&graph, order, 9, None);
+        assert_eq!(order._inner, initial);
+        assert_eq!(crossings, 9);
+        assert!(optimal);
+    }
+
+    #[test]
+    fn ordering_reports_optimality() {
+        let (mut graph, _) = k_3_3();
+        for (exact, exact_threshold, expected) in
+            [(false, 30, false), (true, 5, false), (true, 6, true)]
+        {
+            let options = SweepOptions {
+                exact,
+                exact_threshold,
+                ..UNLIMITED
+            };
+            let (order, optimal) = ordering(
+                &mut graph,
+                CrossingMinimization::Barycenter,
+                true,
+                options,
+                &Control::default(),
+            );
+            assert_eq!(Order::new(order).crossings(&graph), 9);
+            assert_eq!(optimal, expected);
+        }
     }
 }
@@ -1,23 +1,30 @@
+mod network_simplex;
+mod straighten;
 #[cfg(test)]
 mod tests;
 
-use std::collections::HashMap;
-
 use log::info;
 use petgraph::stable_graph::{NodeIndex, StableDiGraph};
-use petgraph::visit::EdgeRef;
-use petgraph::Direction::Incoming;
+use petgraph::visit::{EdgeRef, NodeIndexable};
+use petgraph::Direction::{Incoming, Outgoing};
 
+pub(super) use self::network_simplex::network_simplex_coordinates;
+pub(super) use self::straighten::straighten;
 use super::{slack, Edge, Vertex};
+use crate::configure::{CoordinateCombination, HDir, VDir};
 
 pub(super) fn create_layouts(
     graph: &mut StableDiGraph<Vertex, Edge>,
     layers: &mut [Vec<NodeIndex>],
     vertex_spacing: usize,
     dummy_size: f64,
-) -> Vec<HashMap<NodeIndex, isize>> {
+) -> Vec<Vec<isize>> {
     info!(target: "coordinate_calculation", "Creating individual layouts for coordinate calculation");
+    // layouts are created in the order down right, down left, up right, up left,
+    // see [layout_index]
     let mut layouts = Vec::new();
+    // conflicts are detected via the positions of the vertices
+    reset_alignment(graph, layers);
     mark_type_1_conflicts(graph, layers);
     // calculate the coordinates for each direction
     for _v_dir in [VDir::Down, VDir::Up] {
@@ -33,9 +40,8 @@ pub(super) fn create_layouts(
             let mut layout = do_horizontal_compaction(graph, layers, vertex_spacing, dummy_size);
             // flip x_coordinates if we went from right to left
             if let HDir::Left = h_dir {
-                layout.values_mut().for_each(|x| *x = -*x);
+                layout.iter_mut().for_each(|x| *x = -*x);
             }
-            // print_to_console(v_dir, graph, &orig_layers, layout.clone(), vertex_spacing);
             layouts.push(layout);
 
             // rotate the graph
@@ -52,71 +58,186 @@ pub(super) fn create_layouts(
     layouts
 }
 
-pub(crate) fn align_to_smallest_width_layout(aligned_layouts: &mut [HashMap<NodeIndex, isize>]) {
+/// Layouts are indexed by vertex index, `vertices` are the vertices of the
+/// graph, since removed dummy vertices leave holes in the layouts.
+pub(crate) fn align_to_smallest_width_layout(
+    aligned_layouts: &mut [Vec<isize>],
+    vertices: &[NodeIndex],
+) {
     info!(target: "coordinate_calculation", "Aligning all layouts to the one with the smallest width");
     // determine minimum and maximum coordinate of each layout, plus the width
     let min_max: Vec<(isize, isize, isize)> = aligned_layouts
         .iter()
         .map(|c| {
-            let min = *c.values().min().unwrap();
-            let max = *c.values().max().unwrap();
+            let min = vertices.iter().map(|v| c[v.index()]).min().unwrap();
+            let max = vertices.iter().map(|v| c[v.index()]).max().unwrap();
             (min, max, max - min)
         })
         .collect();
 
     // determine the layout with the minimum width
-    let min_width = min_max
-        .iter()
-        .enumerate()
-        .min_by(|a, b| a.1 .2.cmp(&b.1 .2))
-        .unwrap()
-        .0;
+    let min_width = min_width_layout(&min_max);
 
     // align all other layouts to the lowest coordinate of the layout with the minimum width,
     for (i, layout) in aligned_layouts.iter_mut().enumerate() {
-        // if i % 2 == 0, then horizontal direction was left
+        // if i % 2 == 0, then the layout was compacted to the left
         let shift = if i % 2 == 0 {
-            min_max[i].0 - min_max[min_width].0
+            min_max[min_width].0 - min_max[i].0
         } else {
             min_max[min_width].1 - min_max[i].1
         };
-        for v in layout.values_mut() {
+        for v in layout.iter_mut() {
             let new = *v + shift;
             *v = new;
         }
     }
 }
 
+fn min_width_layout(min_max: &[(isize, isize, isize)]) -> usize {
+    min_max
+        .iter()
+        .enumerate()
+        .min_by(|a, b| a.1 .2.cmp(&b.1 .2))
+        .unwrap()
+        .0
+}
+
+/// Index of the layout of the given direction in the layouts returned by [create_layouts].
+fn layout_index(v_dir: VDir, h_dir: HDir) -> usize {
+    let v = match v_dir {
+        VDir::Down => 0,
+        VDir::Up => 2,
+    };
+    let h = match h_dir {
+        HDir::Right => 0,
+        HDir::Left => 1,
+    };
+    v + h
+}
+
+/// Combines the aligned layouts into the final x-coordinates, see [CoordinateCombination].
 pub(crate) fn calculate_relative_coords(
-    aligned_layouts: Vec<HashMap<NodeIndex, isize>>,
-) -> Vec<(NodeIndex, isize)> {
-    info!(target: "coordinate_calculation", 
-        "Calculate relative coordinates, by taking average between two medians of absolute x-coordinates for each layout direction");
-    // sort all 4 coordinates per vertex in ascending order
-    for l in &aligned_layouts {
-        let mut v = l.iter().collect::<Vec<_>>();
-        v.sort_by(|a, b| a.0.index().cmp(&b.0.index()));
-        // format to NodeIndex: (x, y), width, height
-        // println!("{v:?}\n");
+    mut aligned_layouts: Vec<Vec<isize>>,
+    vertices: &[NodeIndex],
+    combination: CoordinateCombination,
+) -> Vec<isize> {
+    info!(target: "coordinate_calculation",
+        "Calculate relative coordinates, combining the layouts via {combination:?}");
+    let single = match combination {
+        CoordinateCombination::Direction(v_dir, h_dir) => Some(layout_index(v_dir, h_dir)),
+        CoordinateCombination::MinimumWidth => {
+            let min_max = aligned_layouts
+                .iter()
+                .map(|c| {
+                    let min = vertices.iter().map(|v| c[v.index()]).min().unwrap();
+                    let max = vertices.iter().map(|v| c[v.index()]).max().unwrap();
+                    (min, max, max - min)
+                })
+                .collect::<Vec<_>>();
+            Some(min_width_layout(&min_max))
+        }
+        _ => None,
+    };
+    if let Some(i) = single {
+        return aligned_layouts.swap_remove(i);
     }
-    let mut sorted_layouts = HashMap::new();
-    for k in aligned_layouts.first().unwrap().keys() {
+
+    let mut x_coordinates = vec![0; aligned_layouts[0].len()];
+    for v in vertices {
         let mut vertex_coordinates = [
-            *aligned_layouts.first().unwrap().get(k).unwrap(),
-            *aligned_layouts.get(1).unwrap().get(k).unwrap(),
-            *aligned_layouts.get(2).unwrap().get(k).unwrap(),
-            *aligned_layouts.get(3).unwrap().get(k).unwrap(),
+            aligned_layouts[0][v.index()],
+            aligned_layouts[1][v.index()],
+            aligned_layouts[2][v.index()],
+            aligned_layouts[3][v.index()],
         ];
         vertex_coordinates.sort();
-        sorted_layouts.insert(k, vertex_coordinates);
+        // round down, so vertices on both sides of 0 keep their spacing
+        x_coordinates[v.index()] = match combination {
+            CoordinateCombination::AverageMedian => {
+                (vertex_coordinates[1] + vertex_coordinates[2]).div_euclid(2)
+            }
+            _ => vertex_coordinates.iter().sum::<isize>().div_euclid(4),
+        };
     }
+    x_coordinates
+}
 
-    // create final layout, by averaging the two median values
-    // try to use something like mean
-    sorted_layouts
-        .into_iter()
-        .map(|(k, v)| (*k, (v[0] + v[1] + v[2] + v[3]) / 4))
-        .collect::<Vec<_>>()
+/// Minimum distance between two vertices in the same layer.
+fn separation(
+    graph: &StableDiGraph<Vertex, Edge>,
+    u: NodeIndex,
+    v: NodeIndex,
+    vertex_spacing: usize,
+    dummy_size: f64,
+) -> isize {
+    if graph[u].is_dummy && graph[v].is_dummy {
+        (vertex_spacing as f64 * dummy_size) as isize
+    } else {
+        vertex_spacing as isize
+    }
+}
+
+/// Left-right variant of the balance step described in the paper by Gansner et al.
+///
+/// The total length of the edges of a vertex doesn't change, as long as it
+/// stays between the two weighted medians of its neighbors. Vertices which
+/// are inside of this range are moved to its center, without getting closer to
+/// their neighbors in the same layer than they are allowed to.
+pub(crate) fn balance_coordinates(
+    graph: &StableDiGraph<Vertex, Edge>,
+    layers: &[Vec<NodeIndex>],
+    coordinates: &mut [isize],
+    vertex_spacing: usize,
+    dummy_size: f64,
+) {
+    info!(target: "coordinate_calculation", "Balancing vertices between their neighbors");
+    let separation = |u, v| separation(graph, u, v, vertex_spacing, dummy_size);
+
+    for layer in layers {
+        for (i, v) in layer.iter().enumerate() {
+            let mut neighbors = graph
+                .edges_directed(*v, Incoming)
+                .map(|e| (coordinates[e.source().index()], e.weight().weight))
+                .chain(
+                    graph
+                        .edges_directed(*v, Outgoing)
+                        .map(|e| (coordinates[e.target().index()], e.weight().weight)),
+                )
+                .collect::<Vec<_>>();
+            let total = neighbors.iter().map(|(_, w)| w).sum::<i32>();
+            // an odd weight means there is only one median
+            if total == 0 || total % 2 == 1 {
+                continue;
+            }
+            neighbors.sort();
+            let mut weight = 0;
+            let mut iter = neighbors.iter();
+            let left_median = loop {
+                let (x, w) = iter.next().unwrap();
+                weight += w;
+                if weight >= total / 2 {
+                    break *x;
+                }
+            };
+            let right_median = match iter.next() {
+                Some((x, _)) if weight == total / 2 => *x,
+                _ => left_median,
+            };
+
+            let mut left = left_median;
+            let mut right = right_median;
+            if i > 0 {
+                left = left.max(coordinates[layer[i - 1].index()] + separation(layer[i - 1], *v));
+            }
+            if let Some(u) = layer.get(i + 1) {
+                right = right.min(coordinates[u.index()] - separation(*u, *v));
+            }
+            let x = coordinates[v.index()];
+            if left <= x && x <= right {
+                coordinates[v.index()] = (left + right) / 2;
+            }
+        }
+    }
 }
 
 fn is_incident_to_inner_segment(graph: &StableDiGraph<Vertex, Edge>, id: NodeIndex) -> bool {
@@ -157,7 +278,7 @@ fn mark_type_1_conflicts(graph: &mut StableDiGraph<Vertex, Edge>, layers: &[Vec<
                     }
                 }
             };
-            while l < l_1 {
+            while l <= l_1 {
                 let vertex = next_level[l];
                 let mut upper_neighbors = graph
                     .neighbors_directed(vertex, Incoming)
@@ -242,13 +363,21 @@ fn do_horizontal_compaction(
     layers: &[Vec<NodeIndex>],
     vertex_spacing: usize,
     dummy_size: f64,
-) -> HashMap<NodeIndex, isize> {
+) -> Vec<isize> {
     info!(target: "coordinate_calculation", "calculating coordinates for layout.");
-    let mut x_coordinates = place_blocks(graph, layers, vertex_spacing as isize, dummy_size);
+    // removed dummy vertices leave holes, which are never looked at
+    let mut x_coordinates = place_blocks(graph, layers, vertex_spacing as isize, dummy_size)
+        .into_iter()
+        .map(|x| x.unwrap_or(0))
+        .collect::<Vec<_>>();
     // calculate class shifts
     info!(target: "coordinate_calculation", "move blocks as close together as possible");
     for i in 0..layers.len() {
-        let mut v = layers[i][0];
+        // layers of removed dummy vertices are empty
+        let Some(&first) = layers[i].first() else {
+            continue;
+        };
+        let mut v = first;
         if graph[v].sink == v {
             if graph[graph[v].sink].shift == isize::MAX {
                 let v_sink = graph[v].sink;
@@ -264,10 +393,10 @@ fn do_horizontal_compaction(
                     v = graph[v].align;
                     j += 1;
 
-                    if graph[v].pos > 1 {
+                    if graph[v].pos > 0 {
                         let u = pred(graph[v], layers);
-                        let distance_v_u = *x_coordinates.get(&v).unwrap()
-                            - (*x_coordinates.get(&u).unwrap() + vertex_spacing as isize);
+                        let distance_v_u = x_coordinates[v.index()]
+                            - (x_coordinates[u.index()] + vertex_spacing as isize);
                         let u_sink = graph[u].sink;
                         graph[u_sink].shift = graph[u_sink]
                             .shift
@@ -285,10 +414,7 @@ fn do_horizontal_compaction(
 
     // calculate absolute x-coordinates
     for v in graph.node_indices() {
-        x_coordinates.insert(
-            v,
-            *x_coordinates.get(&v).unwrap() + graph[graph[v].sink].shift,
-        );
+        x_coordinates[v.index()] += graph[graph[v].sink].shift;
     }
     x_coordinates
 }
@@ -298,9 +424,9 @@ fn place_blocks(
     layers: &[Vec<NodeIndex>],
     vertex_spacing: isize,
     dummy_size: f64,
-) -> HashMap<NodeIndex, isize> {
+) -> Vec<Option<isize>> {
     info!(target: "coordinate_calculation", "Placing vertices in blocks.");
-    let mut x_coordinates = HashMap::new();
+    let mut x_coordinates = vec![None; graph.node_bound()];
     // place blocks
     for root in graph
         .node_indices()
@@ -322,14 +448,14 @@ fn place_block(
     graph: &mut StableDiGraph<Vertex, Edge>,
     layers: &[Vec<NodeIndex>],
     root: NodeIndex,
-    x_coordinates: &mut HashMap<NodeIndex, isize>,
+    x_coordinates: &mut [Option<isize>],
     vertex_spacing: isize,
     dummy_size: f64,
 ) {
-    if x_coordinates.get(&root).is_some() {
+    if x_coordinates[root.index()].is_some() {
         return;
     }
-    x_coordinates.insert(root, 0);
+    x_coordinates[root.index()] = Some(0);
     let mut w = root;
     loop {
         if graph[w].pos > 0 {
@@ -345,13 +471,8 @@ fn place_block(
                 } else {
                     0.
                 } as isize;
-                x_coordinates.insert(
-                    root,
-                    *x_coordinates
-                        .get(&root)
-                        .unwrap()
-                        .max(&(x_coordinates.get(&u).unwrap() + vertex_spacing - vertex_size)),
-                );
+                x_coordinates[root.index()] = x_coordinates[root.index()]
+                    .max(x_coordinates[u.index()].map(|x| x + vertex_spacing - vertex_size));
             }
         }
         w = graph[w].align;
@@ -362,7 +483,7 @@ fn place_block(
     // align all other vertices in this block to the x-coordinate of the root
     while graph[w].align != root {
         w = graph[w].align;
-        x_coordinates.insert(w, *x_coordinates.get(&root).unwrap());
+        x_coordinates[w.index()] = x_coordinates[root.index()];
         graph[w].sink = graph[root].sink;
     }
 }
@@ -370,22 +491,8 @@ fn place_block(
 fn pred(vertex: Vertex, layers: &[Vec<NodeIndex>]) -> NodeIndex {
     layers[vertex.rank as usize][vertex.pos - 1]
 }
-/// Represents a layered graph whose vertices have been aligned in blocks.
-/// A root is the highest node in a block, depending on the direction.
-///
-/// It is used to determine classes of a block, calculate the x-coordinates of a block
-/// in regard to its class and shift classes together as close as possible.
-
-/// Represents the horizontal direction in which the algorithm is run
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub(crate) enum HDir {
-    Left,
-    Right,
-}
-
-/// Represents the vertical direction in which the algorithm is run
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub(crate) enum VDir {
-    Up,
-    Down,
-}
+// Represents a layered graph whose vertices have been aligned in blocks.
+// A root is the highest node in a block, depending on the direction.
+//
+// It is used to determine classes of a block, calculate the x-coordinates of a block
+// in regard to its class and shift classes together as close as possible.
@@ -0,0 +1,84 @@
+//! Coordinate assignment via network simplex, as described in the paper by
+//! Gansner et al.
+//!
+//! The x-coordinates are the ranks of an auxiliary graph. For each edge `(u, v)`
+//! of the layered graph, it contains a new vertex `n` and the edges `(n, u)`
+//! and `(n, v)`, so the length of these edges is minimized if `u` and `v` are
+//! placed above each other. Vertices which are next to each other in a layer
+//! are connected via an edge without weight, whose minimum length is the
+//! separation of both vertices.
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences, NodeIndexable};
+
+use super::separation;
+use crate::algorithm::{p1_layering as p1, Control, Edge, Vertex};
+
+/// Factor for the weight of an edge, indexed by the number of its endpoints
+/// which are dummy vertices, so long edges are favored to be straight.
+const OMEGA: [i32; 3] = [1, 2, 8];
+
+/// Calculates the x-coordinates, starting from the feasible coordinates
+/// `initial`, e.g. the result of Brandes and Koepf. A good initial solution
+/// means fewer iterations of the network simplex.
+pub(crate) fn network_simplex_coordinates(
+    graph: &StableDiGraph<Vertex, Edge>,
+    layers: &[Vec<NodeIndex>],
+    initial: &[isize],
+    vertex_spacing: usize,
+    dummy_size: f64,
+    search_size: usize,
+    max_iterations: usize,
+) -> Vec<isize> {
+    let mut auxiliary = StableDiGraph::<Vertex, Edge>::new();
+    let mut vertices = vec![NodeIndex::end(); graph.node_bound()];
+    for v in graph.node_indices() {
+        vertices[v.index()] = auxiliary.add_node(Vertex {
+            rank: initial[v.index()] as i32,
+            ..Default::default()
+        });
+    }
+
+    for edge in graph.edge_references() {
+        let (u, v) = (edge.source(), edge.target());
+        let dummies = graph[u].is_dummy as usize + graph[v].is_dummy as usize;
+        let auxiliary_edge = Edge {
+            weight: edge.weight().weight * OMEGA[dummies],
+            ..Default::default()
+        };
+        // one of the edges is tight
+        let n = auxiliary.add_node(Vertex {
+            rank: initial[u.index()].min(initial[v.index()]) as i32,
+            ..Default::default()
+        });
+        auxiliary.add_edge(n, vertices[u.index()], auxiliary_edge);
+        auxiliary.add_edge(n, vertices[v.index()], auxiliary_edge);
+    }
+
+    for layer in layers {
+        for pair in layer.windows(2) {
+            let (u, v) = (pair[0], pair[1]);
+            let separation_edge = Edge {
+                weight: 0,
+                min_length: separation(graph, u, v, vertex_spacing, dummy_size) as i32,
+                ..Default::default()
+            };
+            auxiliary.add_edge(vertices[u.index()], vertices[v.index()], separation_edge);
+        }
+    }
+
+    // the build is only cancelled after the coordinate assignment
+    p1::minimize_edge_length(
+        &mut auxiliary,
+        0,
+        false,
+        search_size,
+        max_iterations,
+        &Control::default(),
+    );
+
+    let mut x_coordinates = vec![0; graph.node_bound()];
+    for v in graph.node_indices() {
+        x_coordinates[v.index()] = auxiliary[vertices[v.index()]].rank as isize;
+    }
+    x_coordinates
+}
@@ -0,0 +1,183 @@
+//! Post-pass which straightens edges after the coordinates were assigned.
+//!
+//! Vertices and long edges, i.e. chains of dummy vertices, are moved one after
+//! another towards the weighted mean of the coordinates of their neighbors.
+//! They are aligned with the neighbor closest to the mean, which straightens
+//! their edge, or placed at the mean if no neighbor can be reached. A vertex
+//! never moves past its neighbors in the same layer, so the order within the
+//! layers and the minimum spacing are kept. Aligning a block with a neighbor
+//! doesn't necessarily shorten the edges, so the blocks may keep moving, until
+//! the number of passes is reached.
+use std::ops::{Add, Sub};
+
+use log::{debug, info};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::visit::{EdgeRef, NodeIndexable};
+use petgraph::Direction::{Incoming, Outgoing};
+
+use super::separation;
+use crate::algorithm::{Edge, Vertex};
+
+/// Numeric type of the coordinates which are straightened.
+pub(crate) trait Coordinate:
+    Copy + PartialOrd + Add<Output = Self> + Sub<Output = Self>
+{
+    fn from_f64(v: f64) -> Self;
+    fn to_f64(self) -> f64;
+}
+
+impl Coordinate for isize {
+    fn from_f64(v: f64) -> Self {
+        v.round() as isize
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Coordinate for f64 {
+    fn from_f64(v: f64) -> Self {
+        v
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+/// Moves blocks towards the weighted mean of their neighbors, for at most
+/// `passes` passes or until no block moves anymore.
+pub(crate) fn straighten<T: Coordinate>(
+    graph: &StableDiGraph<Vertex, Edge>,
+    layers: &[Vec<NodeIndex>],
+    coordinates: &mut [T],
+    vertex_spacing: usize,
+    dummy_size: f64,
+    passes: usize,
+) {
+    info!(target: "coordinate_calculation", "Straightening edges, passes: {passes}");
+    let separation = |u, v| T::from_f64(separation(graph, u, v, vertex_spacing, dummy_size) as f64);
+    // rank and position of each vertex
+    let mut positions = vec![(0, 0); graph.node_bound()];
+    for (rank, layer) in layers.iter().enumerate() {
+        for (pos, v) in layer.iter().enumerate() {
+            positions[v.index()] = (rank, pos);
+        }
+    }
+
+    for pass in 0..passes {
+        let mut moved = false;
+        for block in blocks(graph, layers, coordinates) {
+            let x = coordinates[block[0].index()];
+            let neighbors = block
+                .iter()
+                .flat_map(|v| {
+                    graph
+                        .edges_directed(*v, Incoming)
+                        .map(|e| (e.source(), e.weight().weight))
+                        .chain(
+                            graph
+                                .edges_directed(*v, Outgoing)
+                                .map(|e| (e.target(), e.weight().weight)),
+                        )
+                })
+                .map(|(n, w)| (coordinates[n.index()], w))
+                .filter(|(n, _)| *n != x)
+                .collect::<Vec<_>>();
+            let (sum, total) = neighbors.iter().fold((0., 0.), |(sum, total), (n, w)| {
+                (sum + n.to_f64() * *w as f64, total + *w as f64)
+            });
+            if total == 0. {
+                continue;
+            }
+            let mean = sum / total;
+
+            // every vertex of the block has to stay between its neighbors in the same layer
+            let mut left = None;
+            let mut right = None;
+            for v in &block {
+                let (rank, pos) = positions[v.index()];
+                let layer = &layers[rank];
+                if pos > 0 {
+                    let u = layer[pos - 1];
+                    let x = coordinates[u.index()] + separation(u, *v);
+                    if left.is_none_or(|left| x > left) {
+                        left = Some(x);
+                    }
+                }
+                if let Some(u) = layer.get(pos + 1) {
+                    let x = coordinates[u.index()] - separation(*u, *v);
+                    if right.is_none_or(|right| x < right) {
+                        right = Some(x);
+                    }
+                }
+            }
+            let is_free =
+                |x: T| left.is_none_or(|left| x >= left) && right.is_none_or(|right| x <= right);
+
+            // aligning the block with a neighbor straightens their edge, so
+            // the closest neighbor to the mean is preferred
+            let aligned = neighbors
+                .iter()
+                .map(|(n, _)| *n)
+                .filter(|n| is_free(*n))
+                .min_by(|a, b| {
+                    (a.to_f64() - mean)
+                        .abs()
+                        .total_cmp(&(b.to_f64() - mean).abs())
+                });
+            let target = match aligned {
+                Some(n) => n,
+                None => {
+                    let mut target = T::from_f64(mean);
+                    if let Some(left) = left.filter(|left| target < *left) {
+                        target = left;
+                    }
+                    if let Some(right) = right.filter(|right| target > *right) {
+                        target = right;
+                    }
+                    target
+                }
+            };
+            if target != x {
+                moved = true;
+                for v in block {
+                    coordinates[v.index()] = target;
+                }
+            }
+        }
+        if !moved {
+            debug!(target: "coordinate_calculation", "Straightening converged after {pass} passes");
+            break;
+        }
+    }
+}
+
+/// Returns the vertices which are moved together, i.e. paths of vertices
+/// which are connected via vertical edges, from top to bottom.
+fn blocks<T: Coordinate>(
+    graph: &StableDiGraph<Vertex, Edge>,
+    layers: &[Vec<NodeIndex>],
+    coordinates: &[T],
+) -> Vec<Vec<NodeIndex>> {
+    let is_vertical = |u: NodeIndex, v: NodeIndex| coordinates[u.index()] == coordinates[v.index()];
+    let mut blocks = Vec::new();
+    for &v in layers.iter().flatten() {
+        if graph
+            .neighbors_directed(v, Incoming)
+            .any(|u| is_vertical(u, v))
+        {
+            continue;
+        }
+        let mut block = vec![v];
+        while let Some(w) = graph
+            .neighbors_directed(block[block.len() - 1], Outgoing)
+            .find(|w| is_vertical(v, *w))
+        {
+            block.push(w);
+        }
+        blocks.push(block);
+    }
+    blocks
+}
@@ -40,7 +40,7 @@ fn create_test_layout() -> (StableDiGraph<Vertex, Edge>, Vec<Vec<NodeIndex>>) {
         (25, 15),
     ];
 
-    let mut graph = StableDiGraph::<Vertex, Edge>::from_edges(&edges);
+    let mut graph = StableDiGraph::<Vertex, Edge>::from_edges(edges);
     let layers: Vec<Vec<NodeIndex>> = [
         vec![0, 1],
         vec![2, 3, 16, 4, 17, 18, 5, 6],
@@ -76,6 +76,21 @@ fn type_1() {
     assert!(graph[graph.find_edge(9.into(), 22.into()).unwrap()].has_type_1_conflict);
 }
 
+#[test]
+fn type_1_last_vertex_of_layer() {
+    // the edge 0 -> 3 crosses the inner segment 1 -> 2
+    let mut graph = StableDiGraph::<Vertex, Edge>::from_edges([(0, 3), (1, 2)]);
+    let layers: Vec<Vec<NodeIndex>> = vec![vec![0.into(), 1.into()], vec![2.into(), 3.into()]];
+    for (rank, row) in layers.iter().enumerate() {
+        for (pos, v) in row.iter().enumerate() {
+            graph[*v] = Vertex::new_test_p3(*v, rank as i32, pos, [1, 2].contains(&v.index()));
+        }
+    }
+    mark_type_1_conflicts(&mut graph, &layers);
+    assert!(graph[graph.find_edge(0.into(), 3.into()).unwrap()].has_type_1_conflict);
+    assert!(!graph[graph.find_edge(1.into(), 2.into()).unwrap()].has_type_1_conflict);
+}
+
 #[test]
 fn alignment_down_right() {
     let (mut g, mut l) = create_test_layout();
@@ -155,21 +170,13 @@ fn alignment_down_left() {
         assert_eq!(g[NodeIndex::from(n)].root, 0.into());
     }
     // block root 1
-    for n in [1] {
-        assert_eq!(g[NodeIndex::from(n)].root, 1.into());
-    }
+    assert_eq!(g[NodeIndex::from(1)].root, 1.into());
     // block root 2
-    for n in [2] {
-        assert_eq!(g[NodeIndex::from(n)].root, 2.into());
-    }
+    assert_eq!(g[NodeIndex::from(2)].root, 2.into());
     // block root 3
-    for n in [3] {
-        assert_eq!(g[NodeIndex::from(n)].root, 3.into());
-    }
+    assert_eq!(g[NodeIndex::from(3)].root, 3.into());
     // block root 16
-    for n in [16] {
-        assert_eq!(g[NodeIndex::from(n)].root, 16.into());
-    }
+    assert_eq!(g[NodeIndex::from(16)].root, 16.into());
     // block root 4
     for n in [4, 8] {
         assert_eq!(g[NodeIndex::from(n)].root, 4.into());
@@ -199,9 +206,7 @@ fn alignment_down_left() {
         assert_eq!(g[NodeIndex::from(n)].root, 10.into());
     }
     // block root 22
-    for n in [22] {
-        assert_eq!(g[NodeIndex::from(n)].root, 22.into());
-    }
+    assert_eq!(g[NodeIndex::from(22)].root, 22.into());
 }
 
 #[test]
@@ -311,7 +316,7 @@ fn alignment_up_left() {
 #[test]
 fn place_blocks() {
     let (mut g, mut l) = create_test_layout();
-    mark_type_1_conflicts(&mut g, &mut l);
+    mark_type_1_conflicts(&mut g, &l);
     create_vertical_alignments(&mut g, &mut l);
 
     let block_1: Vec<NodeIndex> = [
@@ -336,3 +341,113 @@ fn place_blocks() {
         assert_eq!(g[v].sink, 7.into());
     }
 }
+
+#[test]
+fn balance_coordinates_centers_vertex() {
+    let mut g = StableDiGraph::<Vertex, Edge>::from_edges([(0, 1), (1, 2)]);
+    let layers = vec![vec![0.into()], vec![1.into()], vec![2.into()]];
+    let mut x_coordinates = vec![0, 0, 20];
+
+    super::balance_coordinates(&g, &layers, &mut x_coordinates, 10, 1.);
+    assert_eq!(x_coordinates[1], 10);
+
+    // a vertex to the right leaves no room to move
+    let v = g.add_node(Vertex::default());
+    let layers = vec![vec![0.into()], vec![1.into(), v], vec![2.into()]];
+    let mut x_coordinates = vec![0, 0, 20, 10];
+
+    super::balance_coordinates(&g, &layers, &mut x_coordinates, 10, 1.);
+    assert_eq!(x_coordinates[1], 0);
+}
+
+#[test]
+fn combine_layouts() {
+    use super::calculate_relative_coords;
+    use crate::configure::{CoordinateCombination, HDir, VDir};
+
+    let vertices = [0.into(), 1.into()];
+    // down right, down left, up right, up left
+    let layouts = vec![vec![0, 10], vec![-3, 20], vec![1, 11], vec![-10, 30]];
+    let combine = |combination| calculate_relative_coords(layouts.clone(), &vertices, combination);
+    assert_eq!(combine(CoordinateCombination::Average), vec![-3, 17]);
+    assert_eq!(combine(CoordinateCombination::AverageMedian), vec![-2, 15]);
+    assert_eq!(combine(CoordinateCombination::MinimumWidth), vec![0, 10]);
+    assert_eq!(
+        combine(CoordinateCombination::Direction(VDir::Up, HDir::Right)),
+        vec![1, 11]
+    );
+    assert_eq!(
+        combine(CoordinateCombination::Direction(VDir::Down, HDir::Left)),
+        vec![-3, 20]
+    );
+}
+
+#[test]
+fn network_simplex_coordinates() {
+    use super::network_simplex_coordinates;
+
+    let (g, layers) = create_test_layout();
+    // every vertex is placed at its position in the layer
+    let mut initial = vec![0; g.node_count()];
+    for layer in &layers {
+        for (pos, v) in layer.iter().enumerate() {
+            initial[v.index()] = pos as isize * 10;
+        }
+    }
+    let x = network_simplex_coordinates(&g, &layers, &initial, 10, 1., 30, usize::MAX);
+
+    for layer in &layers {
+        for pair in layer.windows(2) {
+            assert!(x[pair[1].index()] - x[pair[0].index()] >= 10);
+        }
+    }
+    // long edges are drawn as straight lines
+    for e in g.edge_indices() {
+        let (u, v) = g.edge_endpoints(e).unwrap();
+        if g[u].is_dummy && g[v].is_dummy {
+            assert_eq!(x[u.index()], x[v.index()]);
+        }
+    }
+}
+
+#[test]
+fn straighten_keeps_order_and_spacing() {
+    use super::straighten;
+    use petgraph::visit::NodeIndexable;
+
+    let (g, layers) = create_test_layout();
+    // an inconvenient layout, which places every vertex at its position in the layer
+    let mut x = vec![0isize; g.node_bound()];
+    for layer in &layers {
+        for (pos, v) in layer.iter().enumerate() {
+            x[v.index()] = pos as isize * 10;
+        }
+    }
+    let length = |x: &[isize]| {
+        g.edge_indices()
+            .map(|e| g.edge_endpoints(e).unwrap())
+            .map(|(u, v)| (x[u.index()] - x[v.index()]).abs())
+            .sum::<isize>()
+    };
+    let before = length(&x);
+    straighten(&g, &layers, &mut x, 10, 1., 10);
+
+    for layer in &layers {
+        for pair in layer.windows(2) {
+            assert!(x[pair[1].index()] - x[pair[0].index()] >= 10);
+        }
+    }
+    assert!(length(&x) < before);
+}
+
+#[test]
+fn straighten_float_coordinates() {
+    use super::straighten;
+
+    let g = StableDiGraph::<Vertex, Edge>::from_edges([(0, 1), (1, 2)]);
+    let layers = vec![vec![0.into()], vec![1.into()], vec![2.into()]];
+    let mut x = vec![0., 5., 20.];
+    straighten(&g, &layers, &mut x, 10, 1., 10);
+    assert_eq!(x[0], x[1]);
+    assert_eq!(x[1], x[2]);
+}
@@ -0,0 +1,123 @@
+use log::info;
+use petgraph::stable_graph::StableDiGraph;
+
+use super::vertex_shapes;
+use crate::algorithm::{Edge, Vertex};
+use crate::{EdgeRoute, Shape, VertexShape};
+
+/// Clips the first and the last segment of each route to the outlines of the
+/// tail and the head, so the routes start and end at the outline instead of
+/// the center of the vertices.
+///
+/// Routes of `curves` are the control points of bezier curves, whose first and
+/// last curve lead straight out of the vertex. These curves are removed.
+pub(crate) fn clip_routes(
+    graph: &StableDiGraph<Vertex, Edge>,
+    routes: &mut [EdgeRoute<usize>],
+    curves: bool,
+) {
+    info!(target: "edge_routing", "Clipping edges to the shapes of the vertices");
+    let shapes = vertex_shapes(graph);
+    for route in routes {
+        let points = &mut route.points;
+        if points.len() < 2 {
+            continue;
+        }
+        if let Some(shape) = shapes.get(&route.tail).filter(|s| !s.is_point()) {
+            if curves {
+                points.drain(..3);
+            } else {
+                points[0] = clip(points[0], points[1], shape);
+            }
+            route.tail_clipped = true;
+        }
+        if let Some(shape) = shapes.get(&route.head).filter(|s| !s.is_point()) {
+            let n = points.len();
+            if curves {
+                points.truncate(n - 3);
+            } else {
+                points[n - 1] = clip(points[n - 1], points[n - 2], shape);
+            }
+            route.head_clipped = true;
+        }
+    }
+}
+
+/// Returns the point where the segment from `center`, the center of a vertex
+/// with the given shape, to `to` leaves the outline of the vertex. If `to` is
+/// inside the vertex, `to` is returned.
+fn clip(center: (isize, isize), to: (isize, isize), shape: &VertexShape) -> (isize, isize) {
+    let (dx, dy) = ((to.0 - center.0) as f64, (to.1 - center.1) as f64);
+    if dx == 0. && dy == 0. {
+        return center;
+    }
+    let (a, b) = (shape.width as f64 / 2., shape.height as f64 / 2.);
+    // the segment leaves a rectangle through the side which it reaches first
+    let rectangle = (a / dx.abs()).min(b / dy.abs());
+    let t = match shape.shape {
+        Shape::Rectangle => rectangle,
+        Shape::Ellipse => 1. / ((dx / a).powi(2) + (dy / b).powi(2)).sqrt(),
+        Shape::Diamond => 1. / (dx.abs() / a + dy.abs() / b),
+        Shape::RoundedRectangle => {
+            let r = a.min(b) / 2.;
+            let (x, y) = (rectangle * dx, rectangle * dy);
+            if x.abs() > a - r && y.abs() > b - r {
+                // intersect with the circle of the corner
+                let (kx, ky) = ((a - r).copysign(dx), (b - r).copysign(dy));
+                let p = dx * dx + dy * dy;
+                let q = dx * kx + dy * ky;
+                let c = kx * kx + ky * ky - r * r;
+                (q + (q * q - p * c).sqrt()) / p
+            } else {
+                rectangle
+            }
+        }
+    }
+    .min(1.);
+    (
+        (center.0 as f64 + t * dx).round() as isize,
+        (center.1 as f64 + t * dy).round() as isize,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clip;
+    use crate::{Shape, VertexShape};
+
+    #[test]
+    fn clip_to_shapes() {
+        let shape = |shape| VertexShape::new(shape, 40, 20);
+        let rectangle = shape(Shape::Rectangle);
+        assert_eq!(clip((0, 0), (0, -100), &rectangle), (0, -10));
+        assert_eq!(clip((0, 0), (100, 100), &rectangle), (10, 10));
+        assert_eq!(clip((0, 0), (100, 10), &rectangle), (20, 2));
+
+        let ellipse = shape(Shape::Ellipse);
+        assert_eq!(clip((0, 0), (0, -100), &ellipse), (0, -10));
+        assert_eq!(clip((0, 0), (-100, 0), &ellipse), (-20, 0));
+        // 1 / sqrt(1 / 400 + 1 / 100)
+        assert_eq!(clip((10, 10), (110, 110), &ellipse), (19, 19));
+
+        let diamond = shape(Shape::Diamond);
+        assert_eq!(clip((0, 0), (0, 100), &diamond), (0, 10));
+        assert_eq!(clip((0, 0), (100, 100), &diamond), (7, 7));
+    }
+
+    #[test]
+    fn clip_to_rounded_corner() {
+        let shape = VertexShape::new(Shape::RoundedRectangle, 40, 40);
+        // the straight sides are the same as for a rectangle
+        assert_eq!(clip((0, 0), (0, -100), &shape), (0, -20));
+        assert_eq!(clip((0, 0), (100, 20), &shape), (20, 4));
+        // the corner is a quarter circle of radius 10 around (10, 10)
+        assert_eq!(clip((0, 0), (100, 100), &shape), (17, 17));
+    }
+
+    #[test]
+    fn clip_keeps_points_inside() {
+        let shape = VertexShape::new(Shape::Rectangle, 40, 20);
+        assert_eq!(clip((0, 0), (5, 5), &shape), (5, 5));
+        assert_eq!(clip((0, 0), (0, 0), &shape), (0, 0));
+    }
+}
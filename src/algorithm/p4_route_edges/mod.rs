@@ -0,0 +1,105 @@
+//! Routes the edges through the final coordinates of the vertices.
+//!
+//! By default, edges are drawn as polylines through the dummy vertices of
+//! their long edges. With [crate::configure::EdgeRouting::Orthogonal], edges
+//! consist of horizontal and vertical segments only, see [orthogonal_routes].
+//! With [crate::configure::EdgeRouting::Spline], edges are smooth curves
+//! through the dummy vertices, see [spline_routes]. Afterwards the routes are
+//! clipped to the shapes of the vertices, see [clip_routes].
+mod clip;
+mod orthogonal;
+mod spline;
+
+use std::collections::HashMap;
+
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction::Outgoing;
+
+pub(super) use self::clip::clip_routes;
+pub(super) use self::orthogonal::orthogonal_routes;
+pub(super) use self::spline::spline_routes;
+use super::{Edge, Vertex};
+use crate::{EdgeRoute, VertexShape};
+
+/// Follows each edge from its tail through the chain of dummy vertices
+/// to its head and collects the coordinates along the way.
+///
+/// Edges which where reversed during cycle removal are turned around again.
+pub(super) fn polyline_routes(
+    graph: &StableDiGraph<Vertex, Edge>,
+    coordinates: &[(isize, isize)],
+) -> Vec<EdgeRoute<usize>> {
+    follow_edges(graph, |u, v, points| {
+        if points.is_empty() {
+            points.push(coordinates[u.index()]);
+        }
+        points.push(coordinates[v.index()]);
+    })
+}
+
+/// Returns the number of points of a polyline at which it changes its
+/// direction.
+pub(super) fn count_bends(points: &[(isize, isize)]) -> usize {
+    let mut points = points.to_vec();
+    points.dedup();
+    points
+        .windows(3)
+        .filter(|w| {
+            let (a, b) = (
+                (w[1].0 - w[0].0, w[1].1 - w[0].1),
+                (w[2].0 - w[1].0, w[2].1 - w[1].1),
+            );
+            a.0 * b.1 != a.1 * b.0
+        })
+        .count()
+}
+
+/// Calls `route` for each edge of the layered graph, in the order of the
+/// original edges, which are followed from tail to head through their dummy
+/// vertices. `route` appends the points from the first to the second vertex,
+/// including the first vertex if the route is still empty.
+fn follow_edges(
+    graph: &StableDiGraph<Vertex, Edge>,
+    mut route: impl FnMut(NodeIndex, NodeIndex, &mut Vec<(isize, isize)>),
+) -> Vec<EdgeRoute<usize>> {
+    let mut routes = Vec::new();
+    for v in graph.node_indices().filter(|v| !graph[*v].is_dummy) {
+        for edge in graph.edges_directed(v, Outgoing) {
+            let mut points = Vec::new();
+            let mut tail = v;
+            let mut head = edge.target();
+            route(tail, head, &mut points);
+            while graph[head].is_dummy {
+                tail = head;
+                head = graph.neighbors_directed(head, Outgoing).next().unwrap();
+                route(tail, head, &mut points);
+            }
+
+            let (mut tail, mut head) = (graph[v].id, graph[head].id);
+            let reversed = edge.weight().is_reversed;
+            if reversed {
+                points.reverse();
+                std::mem::swap(&mut tail, &mut head);
+            }
+            routes.push(EdgeRoute {
+                tail,
+                head,
+                points,
+                reversed,
+                tail_clipped: false,
+                head_clipped: false,
+            });
+        }
+    }
+    routes
+}
+
+/// Returns the shapes of the vertices, by their id.
+fn vertex_shapes(graph: &StableDiGraph<Vertex, Edge>) -> HashMap<usize, VertexShape> {
+    graph
+        .node_weights()
+        .filter(|v| !v.is_dummy)
+        .map(|v| (v.id, v.shape))
+        .collect()
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use log::{debug, info};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences, NodeIndexable};
+
+use super::follow_edges;
+use crate::algorithm::p0_cycle_removal::remove_cycles;
+use crate::algorithm::p1_layering::ranking::init_rank;
+use crate::algorithm::{Edge, Vertex};
+use crate::configure::CycleRemoval;
+use crate::EdgeRoute;
+
+/// Penalty for placing two segments in an order, in which the vertical
+/// segment of one overlaps with the vertical segment of the other.
+const OVERLAP: i32 = 3;
+
+/// Routes the edges with horizontal and vertical segments only.
+///
+/// An edge between two vertices with different x-coordinates leaves its
+/// upper vertex downwards, turns onto a horizontal track between the two
+/// layers and enters its lower vertex from above. Horizontal segments which
+/// overlap are placed on different tracks, which are ordered so that as few
+/// segments as possible cross. The distance between two layers grows with
+/// the number of tracks needed between them.
+///
+/// Returns the coordinates of all vertices, with the new y-coordinates, and
+/// the routes of the edges, which only contain the points where the route
+/// changes its direction.
+pub(crate) fn orthogonal_routes(
+    graph: &StableDiGraph<Vertex, Edge>,
+    layers: &[Vec<NodeIndex>],
+    x_coordinates: &[isize],
+    vertex_spacing: usize,
+) -> (Vec<(isize, isize)>, Vec<EdgeRoute<usize>>) {
+    info!(target: "edge_routing", "Routing edges orthogonally");
+    let mut ranks = vec![0; graph.node_bound()];
+    for (rank, layer) in layers.iter().enumerate() {
+        for v in layer {
+            ranks[v.index()] = rank;
+        }
+    }
+
+    // horizontal segments between each pair of adjacent layers,
+    // parallel edges share the same segment
+    let mut segments = vec![Vec::new(); layers.len()];
+    for edge in graph.edge_references() {
+        let (u, v) = (edge.source(), edge.target());
+        if x_coordinates[u.index()] != x_coordinates[v.index()]
+            && !segments[ranks[u.index()]].contains(&(u, v))
+        {
+            segments[ranks[u.index()]].push((u, v));
+        }
+    }
+
+    let track_spacing = (vertex_spacing as isize / 2).max(1);
+    let mut layer_y = vec![0; layers.len()];
+    let mut track_y = HashMap::new();
+    for rank in 0..layers.len().saturating_sub(1) {
+        let (tracks, count) = assign_tracks(
+            &segments[rank]
+                .iter()
+                .map(|(u, v)| (x_coordinates[u.index()], x_coordinates[v.index()]))
+                .collect::<Vec<_>>(),
+        );
+        debug!(target: "edge_routing", "{count} tracks between layers {rank} and {}", rank + 1);
+        let count = count as isize;
+        let gap = (vertex_spacing as isize).max((count + 1) * track_spacing);
+        for (segment, track) in segments[rank].iter().zip(tracks) {
+            let y = layer_y[rank] - (track as isize + 1) * gap / (count + 1);
+            track_y.insert(*segment, y);
+        }
+        layer_y[rank + 1] = layer_y[rank] - gap;
+    }
+
+    let mut coordinates = vec![(0, 0); graph.node_bound()];
+    for v in graph.node_indices() {
+        coordinates[v.index()] = (x_coordinates[v.index()], layer_y[ranks[v.index()]]);
+    }
+
+    let mut routes = follow_edges(graph, |u, v, points| {
+        let ((x_u, y_u), (x_v, y_v)) = (coordinates[u.index()], coordinates[v.index()]);
+        if points.is_empty() {
+            points.push((x_u, y_u));
+        }
+        if let Some(y) = track_y.get(&(u, v)) {
+            points.push((x_u, *y));
+            points.push((x_v, *y));
+        }
+        points.push((x_v, y_v));
+    });
+    for route in &mut routes {
+        remove_straight_points(&mut route.points);
+    }
+    (coordinates, routes)
+}
+
+/// Assigns each horizontal segment, given by the x-coordinates of its upper
+/// and lower end, to a track between two layers, where track 0 is the one
+/// closest to the upper layer. Returns the tracks and the number of tracks.
+///
+/// For each pair of segments which overlap, the order with fewer crossings is
+/// chosen. These orders are turned into a directed acyclic graph, whose
+/// longest path ranking determines the tracks.
+fn assign_tracks(segments: &[(isize, isize)]) -> (Vec<usize>, usize) {
+    let mut graph = StableDiGraph::<Vertex, Edge>::new();
+    let vertices = segments
+        .iter()
+        .map(|_| graph.add_node(Vertex::default()))
+        .collect::<Vec<_>>();
+    for i in 0..segments.len() {
+        for j in i + 1..segments.len() {
+            let (s, t) = (segments[i], segments[j]);
+            if !overlap(s, t) {
+                continue;
+            }
+            let (above, below) = (crossings(s, t), crossings(t, s));
+            let edge = Edge {
+                weight: 1 + (above - below).abs(),
+                ..Default::default()
+            };
+            if above <= below {
+                graph.add_edge(vertices[i], vertices[j], edge);
+            } else {
+                graph.add_edge(vertices[j], vertices[i], edge);
+            }
+        }
+    }
+    remove_cycles(&mut graph, CycleRemoval::WeightedGreedy);
+    init_rank(&mut graph, 1);
+
+    let tracks = vertices
+        .iter()
+        .map(|v| graph[*v].rank as usize)
+        .collect::<Vec<_>>();
+    let count = tracks.iter().max().map_or(0, |max| max + 1);
+    (tracks, count)
+}
+
+/// Returns whether two segments can't be placed on the same track. Segments
+/// which only touch at a shared end, i.e. leave or enter the same vertex in
+/// opposite directions, can share a track.
+fn overlap(s: (isize, isize), t: (isize, isize)) -> bool {
+    let left = s.0.min(s.1).max(t.0.min(t.1));
+    let right = s.0.max(s.1).min(t.0.max(t.1));
+    left < right || left == right && !(s.0 == t.0 && s.0 == left || s.1 == t.1 && s.1 == left)
+}
+
+/// Returns the number of crossings between the segments `s` and `t`, if `s`
+/// is placed on a track above `t`.
+fn crossings(s: (isize, isize), t: (isize, isize)) -> i32 {
+    let inside = |x: isize, (a, b): (isize, isize)| a.min(b) < x && x < a.max(b);
+    // the lower vertical segment of s and the upper vertical segment of t
+    // both pass the track between s and t
+    let mut crossings = inside(s.1, t) as i32 + inside(t.0, s) as i32;
+    if s.1 == t.0 {
+        crossings += OVERLAP;
+    }
+    crossings
+}
+
+/// Removes points which are on a straight line between their neighbors.
+fn remove_straight_points(points: &mut Vec<(isize, isize)>) {
+    points.dedup();
+    let mut i = 1;
+    while i + 1 < points.len() {
+        let (a, b, c) = (points[i - 1], points[i], points[i + 1]);
+        if (a.0 == b.0 && b.0 == c.0) || (a.1 == b.1 && b.1 == c.1) {
+            points.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assign_tracks, remove_straight_points};
+
+    #[test]
+    fn assign_tracks_avoids_crossings() {
+        // the longer segment turns first, so it doesn't cross the vertical
+        // segment of the shorter one
+        assert_eq!(assign_tracks(&[(0, 10), (0, 20)]), (vec![1, 0], 2));
+        assert_eq!(assign_tracks(&[(20, 0), (10, 0)]), (vec![1, 0], 2));
+    }
+
+    #[test]
+    fn assign_tracks_shares_tracks() {
+        assert_eq!(
+            assign_tracks(&[(0, 10), (20, 30), (40, 30)]),
+            (vec![0, 0, 0], 1)
+        );
+        assert_eq!(assign_tracks(&[]), (vec![], 0));
+    }
+
+    #[test]
+    fn assign_tracks_avoids_overlapping_vertical_segments() {
+        // the first segment ends where the second one starts
+        assert_eq!(assign_tracks(&[(0, 10), (10, 20)]), (vec![1, 0], 2));
+    }
+
+    #[test]
+    fn remove_straight_points_keeps_bends() {
+        let mut points = vec![
+            (0, 0),
+            (0, -5),
+            (0, -5),
+            (0, -10),
+            (10, -10),
+            (20, -10),
+            (20, -20),
+        ];
+        remove_straight_points(&mut points);
+        assert_eq!(points, vec![(0, 0), (0, -10), (20, -10), (20, -20)]);
+    }
+}
@@ -0,0 +1,140 @@
+use log::info;
+use petgraph::stable_graph::StableDiGraph;
+
+use super::vertex_shapes;
+use crate::algorithm::{Edge, Vertex};
+use crate::EdgeRoute;
+
+/// Turns the polyline routes of the edges into smooth curves, which consist of
+/// cubic bezier curves.
+///
+/// The curves start at the tail, pass through the dummy vertices of long
+/// edges and end at the head. Each edge leaves and enters its vertices
+/// vertically, so it doesn't cut through the vertex. Vertices without a
+/// [VertexShape](crate::VertexShape) are assumed to take up half of
+/// `vertex_spacing`. Between two layers the curves are monotone in y, so they
+/// only reach the rows of the vertices at their own end points and dummy
+/// vertices.
+///
+/// The points of each route are the control points of the curves, i.e. the
+/// start point followed by three points for each curve, the last of which is
+/// the end point of the curve.
+pub(crate) fn spline_routes(
+    graph: &StableDiGraph<Vertex, Edge>,
+    routes: &mut [EdgeRoute<usize>],
+    vertex_spacing: usize,
+) {
+    info!(target: "edge_routing", "Routing edges as splines");
+    let shapes = vertex_shapes(graph);
+    // half of the height of a vertex
+    let radius = |v| match shapes.get(&v) {
+        Some(shape) if !shape.is_point() => shape.height as isize / 2,
+        _ => vertex_spacing as isize / 4,
+    };
+    for route in routes {
+        route.points = control_points(&route.points, radius(route.tail), radius(route.head));
+    }
+}
+
+/// Turns the points of a polyline into control points of a curve through the
+/// same points.
+///
+/// The first and the last curve lead straight out of the end vertices, which
+/// extend `start` and `end` above and below their center. The tangent at each
+/// dummy vertex is parallel to the line between its neighbors, but at most
+/// diagonal, so the curve only passes the row of the dummy close to it.
+fn control_points(points: &[(isize, isize)], start: isize, end: isize) -> Vec<(isize, isize)> {
+    let n = points.len();
+    if n < 2 {
+        return points.to_vec();
+    }
+    let slopes = (0..n)
+        .map(|i| {
+            if i == 0 || i == n - 1 {
+                return 0.;
+            }
+            let (prev, next) = (points[i - 1], points[i + 1]);
+            let slope = (next.0 - prev.0) as f64 / (next.1 - prev.1) as f64;
+            slope.clamp(-1., 1.)
+        })
+        .collect::<Vec<_>>();
+
+    let (first, last) = (points[0], points[n - 1]);
+    let start = (first.0, first.1 + start * (points[1].1 - first.1).signum());
+    let end = (last.0, last.1 - end * (last.1 - points[n - 2].1).signum());
+
+    let mut control_points = vec![first];
+    push_curve(&mut control_points, first, start, 0., 0.);
+    for i in 0..n - 1 {
+        let from = if i == 0 { start } else { points[i] };
+        let to = if i == n - 2 { end } else { points[i + 1] };
+        push_curve(&mut control_points, from, to, slopes[i], slopes[i + 1]);
+    }
+    push_curve(&mut control_points, end, last, 0., 0.);
+    control_points
+}
+
+/// Appends the control points of a curve from `from` to `to`, whose tangents
+/// at the end points have the given horizontal slopes.
+fn push_curve(
+    control_points: &mut Vec<(isize, isize)>,
+    from: (isize, isize),
+    to: (isize, isize),
+    slope_from: f64,
+    slope_to: f64,
+) {
+    let step = (to.1 - from.1) as f64 / 3.;
+    let control = |(x, y): (isize, isize), slope: f64, step: f64| {
+        (
+            (x as f64 + slope * step).round() as isize,
+            (y as f64 + step).round() as isize,
+        )
+    };
+    control_points.push(control(from, slope_from, step));
+    control_points.push(control(to, slope_to, -step));
+    control_points.push(to);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::control_points;
+
+    #[test]
+    fn control_points_of_short_edge() {
+        let points = control_points(&[(0, 0), (30, -12)], 2, 2);
+        assert_eq!(
+            points,
+            vec![
+                (0, 0),
+                (0, -1),
+                (0, -1),
+                (0, -2),
+                (0, -5),
+                (30, -7),
+                (30, -10),
+                (30, -11),
+                (30, -11),
+                (30, -12),
+            ]
+        );
+    }
+
+    #[test]
+    fn control_points_pass_through_dummy_vertices() {
+        let polyline = [(0, 0), (10, -10), (30, -20), (30, -30)];
+        let points = control_points(&polyline, 2, 2);
+        assert_eq!(points.len(), 3 * (polyline.len() + 1) + 1);
+        assert_eq!(points[0], polyline[0]);
+        assert_eq!(points[6], polyline[1]);
+        assert_eq!(points[9], polyline[2]);
+        assert_eq!(*points.last().unwrap(), polyline[3]);
+        // the curves are monotone in y
+        assert!(points.windows(2).all(|w| w[0].1 >= w[1].1));
+        // the tangent at a dummy vertex is continuous and at most diagonal
+        assert_eq!(points[5], (7, -7));
+        assert_eq!(points[7], (13, -13));
+        // the edge leaves and enters the vertices vertically
+        assert!(points[..5].iter().all(|p| p.0 == 0));
+        assert!(points[points.len() - 5..].iter().all(|p| p.0 == 30));
+    }
+}
@@ -0,0 +1,916 @@
+//! Command line interface for laying out graphs.
+//!
+//! Reads a graph as an edge list, in the DOT language or as JSON and writes
+//! the computed layout as JSON, SVG or DOT. Run `sugiyama --help` for a
+//! description of all options.
+use std::collections::HashMap;
+use std::fmt::{Display, Write as _};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use rust_sugiyama::configure::{
+    Config, CoordinateAssignment, CoordinateCombination, CrossingMinimization, CycleRemoval,
+    EdgeRouting, QualityWeights, RankingType,
+};
+use rust_sugiyama::dot::{parse, DotEdge, DotGraph, DotNode};
+use rust_sugiyama::{Drawing, Shape};
+use serde_json::{json, Value};
+
+static USAGE: &str = "\
+Usage: sugiyama [OPTIONS] [FILE]
+
+Reads a graph from FILE, or from stdin if FILE is missing or '-', and writes
+its layout to stdout.
+
+Input and output:
+  -i, --input-format <FORMAT>    edges, dot or json. Detected from the file
+                                 extension or the input if not set
+  -f, --output-format <FORMAT>   json, svg or dot [default: json]
+  -o, --output <FILE>            write to FILE instead of stdout
+
+Layout:
+      --minimum-length <N>       length between layers [default: 1]
+      --vertex-spacing <N>       minimum spacing between vertices [default: 10]
+      --dummy-vertices <y|n>     include dummy vertices in the layout [default: y]
+      --dummy-size <F>           size of dummy vertices, between 0 and 1 [default: 1.0]
+      --ranking-type <TYPE>      original, minimize, up, down, promote or coffman_graham:N,
+                                 where N is the maximum layer width [default: minimize]
+      --crossing-minimization <HEURISTIC>
+                                 barycenter, median or sifting [default: barycenter]
+      --transpose <y|n>          reduce crossings by swapping vertices [default: y]
+      --cycle-removal <STRATEGY> greedy, dfs, weighted or exact [default: greedy]
+      --balance-ranks <y|n>      move vertices to ranks with fewer vertices [default: n]
+      --balance-coordinates <y|n>
+                                 center vertices between their neighbors [default: n]
+      --combination <STRATEGY>   how the four layouts of the coordinate assignment are
+                                 combined: average, median, min_width, up_left, up_right,
+                                 down_left or down_right [default: average]
+      --coordinate-assignment <ALGORITHM>
+                                 brandes_koepf or network_simplex [default: brandes_koepf]
+      --straighten-passes <N>    move vertices towards their neighbors in up to N passes
+                                 after the coordinate assignment [default: 0]
+      --edge-routing <ROUTING>   polyline, orthogonal or spline [default: polyline]
+      --search-size <N>          candidates compared in each network simplex iteration
+                                 [default: 30]
+      --max-simplex-iterations <N>
+                                 stop the network simplex after N iterations
+      --max-sweeps <N>           maximum number of sweeps during crossing reduction
+      --sweep-patience <N>       stop crossing reduction after N sweeps without
+                                 improvement [default: 4]
+      --transpose-passes <N>     maximum number of transpose passes after each sweep
+      --time-budget <MS>         stop crossing reduction after MS milliseconds and
+                                 use the best order found so far
+      --restarts <N>             restart crossing reduction from N other initial
+                                 orders and keep the best [default: 0]
+      --seed <N>                 seed for random initial orders [default: 0]
+      --exact-crossings <y|n>    minimize crossings exactly for small graphs [default: n]
+      --exact-threshold <N>      maximum number of vertices in two adjacent layers
+                                 for exact crossing minimization [default: 30]
+      --auto                     try several ranking types, crossing minimization heuristics
+                                 and transpose on and off, and keep the best layout. The
+                                 chosen options are printed to stderr
+      --quality-weights <C,A,L>  weights of crossings, area and edge length, which --auto
+                                 uses to compare layouts [default: 1,0.1,0.1]
+      --env                      read the layout options from the RUST_GRAPH_*
+                                 environment variables first
+  -h, --help                     print this message
+
+Input formats:
+  edges   one edge per line, given as two vertex names separated by whitespace
+          or a comma. A line with a single name adds a vertex. '#' starts a comment
+  dot     a graph in the DOT language, its attributes are used for the layout
+  json    {\"vertices\": [...], \"edges\": [[tail, head], ...]}, or only the edges.
+          Vertices are numbers or strings
+
+Exit status:
+  0  the layout was written
+  1  reading or writing a file failed
+  2  invalid arguments
+  3  the input graph is invalid
+";
+
+enum CliError {
+    Usage(String),
+    Io(String),
+    Input(String),
+}
+
+impl CliError {
+    fn code(&self) -> u8 {
+        match self {
+            CliError::Io(_) => 1,
+            CliError::Usage(_) => 2,
+            CliError::Input(_) => 3,
+        }
+    }
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Usage(msg) => write!(f, "{msg}\n\nRun 'sugiyama --help' for usage."),
+            CliError::Io(msg) => f.write_str(msg),
+            CliError::Input(msg) => write!(f, "invalid input: {msg}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum InputFormat {
+    Edges,
+    Dot,
+    Json,
+}
+
+impl TryFrom<String> for InputFormat {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "edges" => Ok(Self::Edges),
+            "dot" => Ok(Self::Dot),
+            "json" => Ok(Self::Json),
+            s => Err(format!("invalid value for input format: {s}")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Json,
+    Svg,
+    Dot,
+}
+
+impl TryFrom<String> for OutputFormat {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "json" => Ok(Self::Json),
+            "svg" => Ok(Self::Svg),
+            "dot" => Ok(Self::Dot),
+            s => Err(format!("invalid value for output format: {s}")),
+        }
+    }
+}
+
+/// Options given on the command line. Layout options which are not set keep
+/// the value of the input, i.e. the default or the one set in a DOT document.
+#[derive(Debug, Default)]
+struct Options {
+    input: Option<String>,
+    output: Option<String>,
+    input_format: Option<InputFormat>,
+    output_format: Option<OutputFormat>,
+    help: bool,
+    env: bool,
+    auto: bool,
+    quality_weights: Option<QualityWeights>,
+    minimum_length: Option<u32>,
+    vertex_spacing: Option<usize>,
+    dummy_vertices: Option<bool>,
+    dummy_size: Option<f64>,
+    ranking_type: Option<RankingType>,
+    c_minimization: Option<CrossingMinimization>,
+    transpose: Option<bool>,
+    cycle_removal: Option<CycleRemoval>,
+    combination: Option<CoordinateCombination>,
+    coordinate_assignment: Option<CoordinateAssignment>,
+    straighten_passes: Option<usize>,
+    edge_routing: Option<EdgeRouting>,
+    balance_ranks: Option<bool>,
+    balance_coordinates: Option<bool>,
+    search_size: Option<usize>,
+    max_simplex_iterations: Option<usize>,
+    max_sweeps: Option<usize>,
+    sweep_patience: Option<usize>,
+    transpose_passes: Option<usize>,
+    time_budget: Option<u64>,
+    restarts: Option<usize>,
+    seed: Option<u64>,
+    exact_crossings: Option<bool>,
+    exact_threshold: Option<usize>,
+}
+
+impl Options {
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // allow --flag=value as well as --flag value
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| CliError::Usage(format!("missing value for {flag}")))
+            };
+            match flag.as_str() {
+                "-h" | "--help" => options.help = true,
+                "--env" => options.env = true,
+                "--auto" => options.auto = true,
+                "--quality-weights" => {
+                    options.quality_weights = Some(convert(value()?, parse_weights)?)
+                }
+                "-i" | "--input-format" => {
+                    options.input_format = Some(convert(value()?, TryFrom::try_from)?)
+                }
+                "-f" | "--output-format" => {
+                    options.output_format = Some(convert(value()?, TryFrom::try_from)?)
+                }
+                "-o" | "--output" => options.output = Some(value()?),
+                "--minimum-length" => {
+                    options.minimum_length = Some(convert(value()?, |v| v.parse::<u32>())?)
+                }
+                "--vertex-spacing" => {
+                    options.vertex_spacing = Some(convert(value()?, |v| v.parse::<usize>())?)
+                }
+                "--dummy-vertices" => options.dummy_vertices = Some(convert(value()?, parse_bool)?),
+                "--dummy-size" => {
+                    options.dummy_size = Some(convert(value()?, |v| v.parse::<f64>())?)
+                }
+                "--ranking-type" => {
+                    options.ranking_type = Some(convert(value()?, TryFrom::try_from)?)
+                }
+                "--crossing-minimization" => {
+                    options.c_minimization = Some(convert(value()?, TryFrom::try_from)?)
+                }
+                "--transpose" => options.transpose = Some(convert(value()?, parse_bool)?),
+                "--cycle-removal" => {
+                    options.cycle_removal = Some(convert(value()?, TryFrom::try_from)?)
+                }
+                "--combination" => {
+                    options.combination = Some(convert(value()?, TryFrom::try_from)?)
+                }
+                "--coordinate-assignment" => {
+                    options.coordinate_assignment = Some(convert(value()?, TryFrom::try_from)?)
+                }
+                "--straighten-passes" => {
+                    options.straighten_passes = Some(convert(value()?, |v| v.parse::<usize>())?)
+                }
+                "--edge-routing" => {
+                    options.edge_routing = Some(convert(value()?, TryFrom::try_from)?)
+                }
+                "--balance-ranks" => options.balance_ranks = Some(convert(value()?, parse_bool)?),
+                "--balance-coordinates" => {
+                    options.balance_coordinates = Some(convert(value()?, parse_bool)?)
+                }
+                "--search-size" => {
+                    options.search_size = Some(convert(value()?, |v| v.parse::<usize>())?)
+                }
+                "--max-simplex-iterations" => {
+                    options.max_simplex_iterations =
+                        Some(convert(value()?, |v| v.parse::<usize>())?)
+                }
+                "--max-sweeps" => {
+                    options.max_sweeps = Some(convert(value()?, |v| v.parse::<usize>())?)
+                }
+                "--sweep-patience" => {
+                    options.sweep_patience = Some(convert(value()?, |v| v.parse::<usize>())?)
+                }
+                "--transpose-passes" => {
+                    options.transpose_passes = Some(convert(value()?, |v| v.parse::<usize>())?)
+                }
+                "--time-budget" => {
+                    options.time_budget = Some(convert(value()?, |v| v.parse::<u64>())?)
+                }
+                "--restarts" => options.restarts = Some(convert(value()?, |v| v.parse::<usize>())?),
+                "--seed" => options.seed = Some(convert(value()?, |v| v.parse::<u64>())?),
+                "--exact-crossings" => {
+                    options.exact_crossings = Some(convert(value()?, parse_bool)?)
+                }
+                "--exact-threshold" => {
+                    options.exact_threshold = Some(convert(value()?, |v| v.parse::<usize>())?)
+                }
+                s if s.starts_with('-') && s != "-" => {
+                    return Err(CliError::Usage(format!("unknown option: {s}")))
+                }
+                _ if options.input.is_some() => {
+                    return Err(CliError::Usage(format!("unexpected argument: {arg}")))
+                }
+                _ => options.input = Some(arg),
+            }
+        }
+        Ok(options)
+    }
+
+    fn apply(&self, mut config: Config) -> Config {
+        if self.env {
+            config = config.read_env();
+        }
+        if let Some(v) = self.minimum_length {
+            config.minimum_length = v;
+        }
+        if let Some(v) = self.vertex_spacing {
+            config.vertex_spacing = v;
+        }
+        if let Some(v) = self.dummy_vertices {
+            config.dummy_vertices = v;
+        }
+        if let Some(v) = self.dummy_size {
+            config.dummy_size = v;
+        }
+        if let Some(v) = self.ranking_type {
+            config.ranking_type = v;
+        }
+        if let Some(v) = self.c_minimization {
+            config.c_minimization = v;
+        }
+        if let Some(v) = self.transpose {
+            config.transpose = v;
+        }
+        if let Some(v) = self.cycle_removal {
+            config.cycle_removal = v;
+        }
+        if let Some(v) = self.combination {
+            config.coordinate_combination = v;
+        }
+        if let Some(v) = self.coordinate_assignment {
+            config.coordinate_assignment = v;
+        }
+        if let Some(v) = self.straighten_passes {
+            config.straighten_passes = v;
+        }
+        if let Some(v) = self.edge_routing {
+            config.edge_routing = v;
+        }
+        if let Some(v) = self.balance_ranks {
+            config.balance_ranks = v;
+        }
+        if let Some(v) = self.balance_coordinates {
+            config.balance_coordinates = v;
+        }
+        if let Some(v) = self.search_size {
+            config.search_size = v;
+        }
+        if let Some(v) = self.max_simplex_iterations {
+            config.max_simplex_iterations = v;
+        }
+        if let Some(v) = self.max_sweeps {
+            config.max_sweeps = v;
+        }
+        if let Some(v) = self.sweep_patience {
+            config.sweep_patience = v;
+        }
+        if let Some(v) = self.transpose_passes {
+            config.transpose_passes = v;
+        }
+        if let Some(v) = self.time_budget {
+            config.crossing_time_budget = Some(Duration::from_millis(v));
+        }
+        if let Some(v) = self.restarts {
+            config.crossing_restarts = v;
+        }
+        if let Some(v) = self.seed {
+            config.seed = v;
+        }
+        if let Some(v) = self.exact_crossings {
+            config.exact_crossings = v;
+        }
+        if let Some(v) = self.exact_threshold {
+            config.exact_crossing_threshold = v;
+        }
+        config
+    }
+}
+
+fn convert<T, E: Display>(
+    value: String,
+    f: impl FnOnce(String) -> Result<T, E>,
+) -> Result<T, CliError> {
+    let shown = value.clone();
+    f(value).map_err(|e| CliError::Usage(format!("invalid value '{shown}': {e}")))
+}
+
+fn parse_bool(value: String) -> Result<bool, String> {
+    match value.as_str() {
+        "y" | "yes" | "true" => Ok(true),
+        "n" | "no" | "false" => Ok(false),
+        v => Err(format!("expected y or n, got {v}")),
+    }
+}
+
+/// Parses the weights of crossings, area and edge length, separated by commas.
+fn parse_weights(value: String) -> Result<QualityWeights, String> {
+    let weights = value
+        .split(',')
+        .map(|w| w.trim().parse::<f64>().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    match weights.as_slice() {
+        &[crossings, area, edge_length] => Ok(QualityWeights {
+            crossings,
+            area,
+            edge_length,
+        }),
+        _ => Err(format!("expected three weights, got {}", weights.len())),
+    }
+}
+
+/// Returns the options which reproduce the choices of the automatic
+/// configuration.
+fn auto_options(config: &Config) -> String {
+    format!(
+        "--ranking-type {} --crossing-minimization {} --transpose {}",
+        config.ranking_type,
+        <&str>::from(config.c_minimization),
+        if config.transpose { "y" } else { "n" }
+    )
+}
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("sugiyama: {e}");
+            ExitCode::from(e.code())
+        }
+    }
+}
+
+fn run(args: impl IntoIterator<Item = String>) -> Result<(), CliError> {
+    let options = Options::parse(args)?;
+    if options.help {
+        print!("{USAGE}");
+        return Ok(());
+    }
+
+    let input = match options.input.as_deref() {
+        None | Some("-") => {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|e| CliError::Io(format!("failed to read stdin: {e}")))?;
+            input
+        }
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| CliError::Io(format!("failed to read {path}: {e}")))?,
+    };
+    let format = options
+        .input_format
+        .unwrap_or_else(|| detect_format(options.input.as_deref(), &input));
+
+    let mut graph = match format {
+        InputFormat::Edges => read_edges(&input)?,
+        InputFormat::Dot => parse(&input).map_err(|e| CliError::Input(e.to_string()))?,
+        InputFormat::Json => read_json(&input)?,
+    };
+    for warning in &graph.warnings {
+        eprintln!("sugiyama: warning: {warning}");
+    }
+    graph.config = options.apply(graph.config);
+
+    let drawings = if options.auto {
+        let weights = options.quality_weights.unwrap_or_default();
+        let (drawings, config) = graph.builder().build_auto(weights);
+        eprintln!("sugiyama: chose {}", auto_options(&config));
+        graph.config = config;
+        drawings
+    } else {
+        graph.builder().build_with_edges()
+    };
+    let output = match options.output_format.unwrap_or(OutputFormat::Json) {
+        OutputFormat::Json => write_json(&graph, &drawings),
+        OutputFormat::Svg => write_svg(&graph, &drawings),
+        OutputFormat::Dot => graph.to_dot(&drawings),
+    };
+
+    match options.output.as_deref() {
+        None | Some("-") => std::io::stdout()
+            .write_all(output.as_bytes())
+            .map_err(|e| CliError::Io(format!("failed to write to stdout: {e}"))),
+        Some(path) => std::fs::write(path, output)
+            .map_err(|e| CliError::Io(format!("failed to write {path}: {e}"))),
+    }
+}
+
+fn detect_format(path: Option<&str>, input: &str) -> InputFormat {
+    match path.and_then(|p| Path::new(p).extension()?.to_str()) {
+        Some("dot" | "gv") => return InputFormat::Dot,
+        Some("json") => return InputFormat::Json,
+        Some("txt" | "edges") => return InputFormat::Edges,
+        _ => (),
+    }
+    let input = input.trim_start();
+    if input.starts_with('{') || input.starts_with('[') {
+        InputFormat::Json
+    } else if input
+        .split_whitespace()
+        .take(3)
+        .any(|w| ["graph", "digraph"].contains(&w.to_lowercase().as_str()))
+    {
+        InputFormat::Dot
+    } else {
+        InputFormat::Edges
+    }
+}
+
+/// Builds the graph vertex by vertex, so vertices keep their names.
+#[derive(Default)]
+struct GraphBuilder {
+    graph: StableDiGraph<DotNode, DotEdge>,
+    ids: HashMap<String, NodeIndex>,
+    warnings: Vec<String>,
+}
+
+impl GraphBuilder {
+    fn vertex(&mut self, name: String) -> NodeIndex {
+        *self.ids.entry(name.clone()).or_insert_with(|| {
+            self.graph.add_node(DotNode {
+                name,
+                ..Default::default()
+            })
+        })
+    }
+
+    /// Adds an edge, self loops are removed like in DOT documents.
+    fn edge(&mut self, tail: String, head: String) {
+        if tail == head {
+            self.warnings.push(format!(
+                "self loops are not supported, removed edge {tail} -> {head}"
+            ));
+            self.vertex(tail);
+            return;
+        }
+        let tail = self.vertex(tail);
+        let head = self.vertex(head);
+        self.graph.add_edge(tail, head, DotEdge::default());
+    }
+
+    fn finish(self) -> DotGraph {
+        DotGraph {
+            name: None,
+            graph: self.graph,
+            config: Config::default(),
+            warnings: self.warnings,
+        }
+    }
+}
+
+fn read_edges(input: &str) -> Result<DotGraph, CliError> {
+    let mut builder = GraphBuilder::default();
+    for (i, line) in input.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let names = line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        match <[String; 2]>::try_from(names) {
+            Ok([tail, head]) => builder.edge(tail, head),
+            Err(names) if names.len() == 1 => {
+                builder.vertex(names.into_iter().next().unwrap());
+            }
+            Err(names) if names.is_empty() => (),
+            Err(_) => {
+                return Err(CliError::Input(format!(
+                    "line {}: expected an edge 'tail head', got '{}'",
+                    i + 1,
+                    line.trim()
+                )))
+            }
+        }
+    }
+    Ok(builder.finish())
+}
+
+fn read_json(input: &str) -> Result<DotGraph, CliError> {
+    let value = serde_json::from_str::<Value>(input)
+        .map_err(|e| CliError::Input(format!("failed to parse JSON: {e}")))?;
+    let (vertices, edges) = match &value {
+        Value::Array(edges) => (None, edges),
+        Value::Object(map) => {
+            let vertices = match map.get("vertices") {
+                Some(Value::Array(v)) => Some(v),
+                None => None,
+                Some(_) => return Err(CliError::Input("'vertices' must be an array".into())),
+            };
+            match map.get("edges") {
+                Some(Value::Array(e)) => (vertices, e),
+                None if vertices.is_some() => (vertices, &Vec::new()),
+                _ => return Err(CliError::Input("'edges' must be an array".into())),
+            }
+        }
+        _ => {
+            return Err(CliError::Input(
+                "expected an object or an array of edges".into(),
+            ))
+        }
+    };
+
+    let name = |v: &Value| match v {
+        Value::Number(n) => Ok(n.to_string()),
+        Value::String(s) => Ok(s.clone()),
+        v => Err(CliError::Input(format!(
+            "vertices must be numbers or strings, got {v}"
+        ))),
+    };
+    let mut builder = GraphBuilder::default();
+    for v in vertices.into_iter().flatten() {
+        builder.vertex(name(v)?);
+    }
+    for e in edges {
+        match e.as_array().map(Vec::as_slice) {
+            Some([tail, head]) => builder.edge(name(tail)?, name(head)?),
+            _ => {
+                return Err(CliError::Input(format!(
+                    "edges must be arrays of two vertices, got {e}"
+                )))
+            }
+        }
+    }
+    if vertices.is_some() && builder.ids.len() > vertices.map_or(0, Vec::len) {
+        return Err(CliError::Input(
+            "edges contain vertices which are not in 'vertices'".into(),
+        ));
+    }
+    Ok(builder.finish())
+}
+
+/// Vertices are written as numbers, if their name is one.
+fn json_id(graph: &DotGraph, v: NodeIndex) -> Value {
+    let name = &graph.graph[v].name;
+    match name.parse::<i64>() {
+        Ok(n) => json!(n),
+        Err(_) => json!(name),
+    }
+}
+
+fn write_json(graph: &DotGraph, drawings: &[Drawing<NodeIndex>]) -> String {
+    let layouts = drawings
+        .iter()
+        .map(|d| {
+            json!({
+                "vertices": d.vertices.iter().map(|(v, (x, y))| json!({
+                    "id": json_id(graph, *v),
+                    "x": x,
+                    "y": y,
+                })).collect::<Vec<_>>(),
+                "edges": d.edges.iter().map(|e| json!({
+                    "tail": json_id(graph, e.tail),
+                    "head": json_id(graph, e.head),
+                    "points": e.points,
+                    "reversed": e.reversed,
+                })).collect::<Vec<_>>(),
+                "width": d.width,
+                "height": d.height,
+                "crossings_optimal": d.crossings_optimal,
+                "metrics": {
+                    "crossings": d.metrics.crossings,
+                    "total_edge_length": d.metrics.total_edge_length,
+                    "max_edge_length": d.metrics.max_edge_length,
+                    "dummy_vertices": d.metrics.dummy_vertices,
+                    "reversed_edges": d.metrics.reversed_edges,
+                    "bends": d.metrics.bends,
+                    "width": d.metrics.width,
+                    "height": d.metrics.height,
+                    "area": d.metrics.area(),
+                    "aspect_ratio": d.metrics.aspect_ratio(),
+                    "max_layer_width": d.metrics.max_layer_width,
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut output = serde_json::to_string_pretty(&layouts).unwrap();
+    output.push('\n');
+    output
+}
+
+fn write_svg(graph: &DotGraph, drawings: &[Drawing<NodeIndex>]) -> String {
+    let spacing = graph.config.vertex_spacing as f64;
+    let radius = spacing / 4.;
+    let arrow = radius / 2.;
+
+    // place the components next to each other, the y-axis of svg points down
+    let mut offset = spacing / 2.;
+    let mut bottom = 0;
+    let mut body = String::new();
+    for d in drawings {
+        let transform = |(x, y): (isize, isize)| (x as f64 + offset, spacing / 2. - y as f64);
+        for e in &d.edges {
+            // routes which were clipped to the shape of the head end at its outline
+            let marker = if e.head_clipped {
+                "arrow-clipped"
+            } else {
+                "arrow"
+            };
+            let points = e
+                .points
+                .iter()
+                .map(|p| {
+                    let (x, y) = transform(*p);
+                    format!("{x},{y}")
+                })
+                .collect::<Vec<_>>();
+            if graph.config.edge_routing == EdgeRouting::Spline {
+                // the points are the start point and three control points per curve
+                let curves = points[1..]
+                    .chunks(3)
+                    .map(|c| format!("C{}", c.join(" ")))
+                    .collect::<Vec<_>>();
+                writeln!(
+                    body,
+                    "  <path d=\"M{} {}\" fill=\"none\" stroke=\"black\" marker-end=\"url(#{marker})\"/>",
+                    points[0],
+                    curves.join(" ")
+                )
+                .unwrap();
+                continue;
+            }
+            writeln!(
+                body,
+                "  <polyline points=\"{}\" fill=\"none\" stroke=\"black\" marker-end=\"url(#{marker})\"/>",
+                points.join(" ")
+            )
+            .unwrap();
+        }
+        for (v, coords) in &d.vertices {
+            let (x, y) = transform(*coords);
+            let shape = graph.graph[*v].shape;
+            let (w, h) = (shape.width as f64, shape.height as f64);
+            let element = match shape.shape {
+                _ if shape.is_point() => format!("circle cx=\"{x}\" cy=\"{y}\" r=\"{radius}\""),
+                Shape::Rectangle | Shape::RoundedRectangle => {
+                    let r = match shape.shape {
+                        Shape::RoundedRectangle => w.min(h) / 4.,
+                        _ => 0.,
+                    };
+                    format!(
+                        "rect x=\"{}\" y=\"{}\" width=\"{w}\" height=\"{h}\" rx=\"{r}\"",
+                        x - w / 2.,
+                        y - h / 2.
+                    )
+                }
+                Shape::Ellipse => format!(
+                    "ellipse cx=\"{x}\" cy=\"{y}\" rx=\"{}\" ry=\"{}\"",
+                    w / 2.,
+                    h / 2.
+                ),
+                Shape::Diamond => format!(
+                    "polygon points=\"{x},{} {},{y} {x},{} {},{y}\"",
+                    y - h / 2.,
+                    x + w / 2.,
+                    y + h / 2.,
+                    x - w / 2.
+                ),
+            };
+            writeln!(body, "  <{element} fill=\"white\" stroke=\"black\"/>").unwrap();
+            writeln!(
+                body,
+                "  <text x=\"{x}\" y=\"{y}\" font-size=\"{radius}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                escape_xml(&graph.graph[*v].to_string())
+            )
+            .unwrap();
+        }
+        offset += d.vertices.iter().map(|(_, (x, _))| *x).max().unwrap_or(0) as f64 + spacing;
+        bottom = bottom.max(d.vertices.iter().map(|(_, (_, y))| -y).max().unwrap_or(0));
+    }
+
+    let width = offset - spacing / 2.;
+    let height = bottom as f64 + spacing;
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n  \
+         <defs>\n    \
+         <marker id=\"arrow\" markerUnits=\"userSpaceOnUse\" markerWidth=\"{arrow}\" markerHeight=\"{arrow}\" \
+         refX=\"{}\" refY=\"{}\" orient=\"auto\" overflow=\"visible\">\n      \
+         <path d=\"M0,0 L{arrow},{} L0,{arrow} z\"/>\n    \
+         </marker>\n    \
+         <marker id=\"arrow-clipped\" markerUnits=\"userSpaceOnUse\" markerWidth=\"{arrow}\" markerHeight=\"{arrow}\" \
+         refX=\"{arrow}\" refY=\"{}\" orient=\"auto\" overflow=\"visible\">\n      \
+         <path d=\"M0,0 L{arrow},{} L0,{arrow} z\"/>\n    \
+         </marker>\n  \
+         </defs>\n{body}</svg>\n",
+        arrow + radius,
+        arrow / 2.,
+        arrow / 2.,
+        arrow / 2.,
+        arrow / 2.,
+    )
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parse_options() {
+        let options = Options::parse(args(
+            "graph.txt -f svg --vertex-spacing 20 --ranking-type=up --transpose n",
+        ))
+        .ok()
+        .unwrap();
+        assert_eq!(options.input.as_deref(), Some("graph.txt"));
+        assert_eq!(options.output_format, Some(OutputFormat::Svg));
+        let config = options.apply(Config::default());
+        assert_eq!(config.vertex_spacing, 20);
+        assert_eq!(config.ranking_type, RankingType::Up);
+        assert!(!config.transpose);
+        assert_eq!(config.minimum_length, Config::default().minimum_length);
+
+        let options = Options::parse(args("--auto --quality-weights=2,0,0.5"))
+            .ok()
+            .unwrap();
+        assert!(options.auto);
+        assert_eq!(
+            options.quality_weights,
+            Some(QualityWeights {
+                crossings: 2.,
+                area: 0.,
+                edge_length: 0.5,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_invalid_options() {
+        let code = |s| Options::parse(args(s)).err().map(|e| e.code());
+        assert_eq!(code("--vertex-spacing"), Some(2));
+        assert_eq!(code("--vertex-spacing ten"), Some(2));
+        assert_eq!(code("--ranking-type sideways"), Some(2));
+        assert_eq!(code("--unknown"), Some(2));
+        assert_eq!(code("a.txt b.txt"), Some(2));
+        assert_eq!(code("--quality-weights 1,2"), Some(2));
+    }
+
+    #[test]
+    fn detect_input_format() {
+        assert_eq!(detect_format(Some("g.gv"), ""), InputFormat::Dot);
+        assert_eq!(detect_format(None, "digraph { a -> b }"), InputFormat::Dot);
+        assert_eq!(detect_format(None, " [[0, 1]]"), InputFormat::Json);
+        assert_eq!(detect_format(None, "0 1\n1 2"), InputFormat::Edges);
+    }
+
+    #[test]
+    fn read_edge_list() {
+        let graph = read_edges("# comment\n0 1\n1,2\n\n3\n").ok().unwrap();
+        assert_eq!(graph.graph.node_count(), 4);
+        assert_eq!(graph.graph.edge_count(), 2);
+        assert_eq!(read_edges("0 1 2").err().map(|e| e.code()), Some(3));
+    }
+
+    #[test]
+    fn self_loops_are_removed() {
+        for graph in [
+            read_edges("a a\na b"),
+            read_json(r#"[["a", "a"], ["a", "b"]]"#),
+        ] {
+            let graph = graph.ok().unwrap();
+            assert_eq!(graph.graph.node_count(), 2);
+            assert_eq!(graph.graph.edge_count(), 1);
+            assert!(graph.warnings[0].contains("self loops"));
+            assert_eq!(graph.builder().build_with_edges()[0].vertices.len(), 2);
+        }
+        // a graph of a single self loop keeps its vertex
+        let graph = read_edges("a a").ok().unwrap();
+        assert_eq!(graph.graph.node_count(), 1);
+        assert_eq!(graph.builder().build().len(), 1);
+    }
+
+    #[test]
+    fn read_json_graph() {
+        let graph = read_json(r#"{"vertices": [5, "a", 7], "edges": [[5, "a"]]}"#)
+            .ok()
+            .unwrap();
+        assert_eq!(graph.graph.node_count(), 3);
+        assert_eq!(graph.graph.edge_count(), 1);
+        assert!(read_json("[[0, 1], [1, 2]]").is_ok());
+        assert!(read_json(r#"{"vertices": [0], "edges": [[0, 1]]}"#).is_err());
+        assert!(read_json("[[0, 1, 2]]").is_err());
+        assert!(read_json("{").is_err());
+    }
+
+    #[test]
+    fn write_json_keeps_ids() {
+        let graph = read_edges("10 20\n20 x").ok().unwrap();
+        let drawings = graph.builder().build_with_edges();
+        let output = serde_json::from_str::<Value>(&write_json(&graph, &drawings)).unwrap();
+        let mut ids = output[0]["vertices"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v["id"].to_string())
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, ["\"x\"", "10", "20"]);
+        assert_eq!(output[0]["edges"].as_array().unwrap().len(), 2);
+    }
+}
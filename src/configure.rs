@@ -1,11 +1,11 @@
-use std::{env, marker::PhantomData};
+use std::{env, fmt::Display, marker::PhantomData, sync::Arc, time::Duration};
 
 use log::{error, trace};
 use petgraph::stable_graph::{NodeIndex, StableDiGraph};
 
 use crate::{
-    algorithm::{self, Edge, Vertex},
-    Layouts,
+    algorithm::{self, Control, Edge, Vertex},
+    CancellationToken, Cancelled, Drawing, Drawings, Layouts, Metrics, Progress, VertexShape,
 };
 
 // Default values for configuration
@@ -16,6 +16,23 @@ pub static RANKING_TYPE_DEFAULT: RankingType = RankingType::MinimizeEdgeLength;
 pub static C_MINIMIZATION_DEFAULT: CrossingMinimization = CrossingMinimization::Barycenter;
 pub static TRANSPOSE_DEFAULT: bool = true;
 pub static DUMMY_SIZE_DEFAULT: f64 = 1.0;
+pub static CYCLE_REMOVAL_DEFAULT: CycleRemoval = CycleRemoval::Greedy;
+pub static BALANCE_RANKS_DEFAULT: bool = false;
+pub static BALANCE_COORDINATES_DEFAULT: bool = false;
+pub static SEARCH_SIZE_DEFAULT: usize = 30;
+pub static MAX_SIMPLEX_ITERATIONS_DEFAULT: usize = usize::MAX;
+pub static MAX_SWEEPS_DEFAULT: usize = usize::MAX;
+pub static SWEEP_PATIENCE_DEFAULT: usize = 4;
+pub static TRANSPOSE_PASSES_DEFAULT: usize = usize::MAX;
+pub static CROSSING_TIME_BUDGET_DEFAULT: Option<Duration> = None;
+pub static CROSSING_RESTARTS_DEFAULT: usize = 0;
+pub static SEED_DEFAULT: u64 = 0;
+pub static EXACT_CROSSINGS_DEFAULT: bool = false;
+pub static EXACT_CROSSING_THRESHOLD_DEFAULT: usize = 30;
+pub static COORDINATE_COMBINATION_DEFAULT: CoordinateCombination = CoordinateCombination::Average;
+pub static COORDINATE_ASSIGNMENT_DEFAULT: CoordinateAssignment = CoordinateAssignment::BrandesKoepf;
+pub static STRAIGHTEN_PASSES_DEFAULT: usize = 0;
+pub static EDGE_ROUTING_DEFAULT: EdgeRouting = EdgeRouting::Polyline;
 
 static ENV_MINIMUM_LENGTH: &str = "RUST_GRAPH_MIN_LEN";
 static ENV_VERTEX_SPACING: &str = "RUST_GRAPH_V_SPACING";
@@ -24,6 +41,23 @@ static ENV_RANKING_TYPE: &str = "RUST_GRAPH_R_TYPE";
 static ENV_CROSSING_MINIMIZATION: &str = "RUST_GRAPH_CROSS_MIN";
 static ENV_TRANSPOSE: &str = "RUST_GRAPH_TRANSPOSE";
 static ENV_DUMMY_SIZE: &str = "RUST_GRAPH_DUMMY_SIZE";
+static ENV_CYCLE_REMOVAL: &str = "RUST_GRAPH_CYCLE_REMOVAL";
+static ENV_BALANCE_RANKS: &str = "RUST_GRAPH_BALANCE_RANKS";
+static ENV_BALANCE_COORDINATES: &str = "RUST_GRAPH_BALANCE_COORDS";
+static ENV_SEARCH_SIZE: &str = "RUST_GRAPH_SEARCH_SIZE";
+static ENV_MAX_SIMPLEX_ITERATIONS: &str = "RUST_GRAPH_MAX_SIMPLEX_ITER";
+static ENV_MAX_SWEEPS: &str = "RUST_GRAPH_MAX_SWEEPS";
+static ENV_SWEEP_PATIENCE: &str = "RUST_GRAPH_SWEEP_PATIENCE";
+static ENV_TRANSPOSE_PASSES: &str = "RUST_GRAPH_TRANSPOSE_PASSES";
+static ENV_CROSSING_TIME_BUDGET: &str = "RUST_GRAPH_CROSS_MIN_BUDGET";
+static ENV_CROSSING_RESTARTS: &str = "RUST_GRAPH_RESTARTS";
+static ENV_SEED: &str = "RUST_GRAPH_SEED";
+static ENV_EXACT_CROSSINGS: &str = "RUST_GRAPH_EXACT_CROSSINGS";
+static ENV_EXACT_CROSSING_THRESHOLD: &str = "RUST_GRAPH_EXACT_THRESHOLD";
+static ENV_COORDINATE_COMBINATION: &str = "RUST_GRAPH_COORD_COMBINATION";
+static ENV_COORDINATE_ASSIGNMENT: &str = "RUST_GRAPH_COORD_ASSIGNMENT";
+static ENV_STRAIGHTEN_PASSES: &str = "RUST_GRAPH_STRAIGHTEN_PASSES";
+static ENV_EDGE_ROUTING: &str = "RUST_GRAPH_EDGE_ROUTING";
 
 pub trait IntoCoordinates {}
 
@@ -53,6 +87,33 @@ macro_rules! read_env {
 /// 4. ranking_type: defines how vertices are places vertically, see [RankingType]
 /// 5. c_minimization: which heuristic to use when minimizing edge crossings, see [CrossingMinimization]
 /// 6. transpose: try to further reduce crossings, by swaping vertices in a layer, may increase runtime significantly
+/// 7. cycle_removal: which edges to reverse in order to remove cycles, see [CycleRemoval]
+/// 8. balance_ranks: move vertices with equal in- and out-weight to feasible ranks with fewer vertices,
+///    only used with [RankingType::MinimizeEdgeLength]
+/// 9. balance_coordinates: center vertices between their neighbors, if this doesn't change the edge lengths
+/// 10. search_size: number of edges with a negative cut value which are compared, before one of them is
+///     replaced in the network simplex, only used with [RankingType::MinimizeEdgeLength]
+/// 11. max_simplex_iterations: maximum number of edges replaced in the network simplex, the ranking is
+///     valid but the edges may be longer than necessary, if this is reached
+/// 12. max_sweeps: maximum number of sweeps through the layers during crossing minimization
+/// 13. sweep_patience: crossing minimization stops after this many sweeps without improvement
+/// 14. transpose_passes: maximum number of passes of transpose after each sweep
+/// 15. crossing_time_budget: time after which crossing minimization stops and returns the best order
+///     found so far, applies to each connected component separately
+/// 16. crossing_restarts: number of times crossing minimization is restarted from another initial order
+///     (breadth first search, input order, then random orders), the order with the fewest crossings is kept
+/// 17. seed: seed for the random initial orders of crossing minimization, layouts are reproducible
+///     for the same seed
+/// 18. exact_crossings: after the heuristics, search the order with the fewest crossings via branch and
+///     bound. [crate::Drawing::crossings_optimal] reports whether the search finished
+/// 19. exact_crossing_threshold: the exact search is only used if no two adjacent layers contain more
+///     vertices than this, otherwise the order of the heuristics is kept
+/// 20. coordinate_combination: how the four layouts of the coordinate assignment are combined into the
+///     final coordinates, see [CoordinateCombination]
+/// 21. coordinate_assignment: which algorithm calculates the x-coordinates, see [CoordinateAssignment]
+/// 22. straighten_passes: maximum number of passes which move vertices and long edges towards the weighted
+///     mean of their neighbors after the coordinate assignment, keeping the order and spacing. 0 disables it
+/// 23. edge_routing: how the routes of the edges are drawn, see [EdgeRouting]
 #[derive(Clone, Copy, Debug)]
 pub struct Config {
     pub minimum_length: u32,
@@ -62,6 +123,23 @@ pub struct Config {
     pub ranking_type: RankingType,
     pub c_minimization: CrossingMinimization,
     pub transpose: bool,
+    pub cycle_removal: CycleRemoval,
+    pub balance_ranks: bool,
+    pub balance_coordinates: bool,
+    pub search_size: usize,
+    pub max_simplex_iterations: usize,
+    pub max_sweeps: usize,
+    pub sweep_patience: usize,
+    pub transpose_passes: usize,
+    pub crossing_time_budget: Option<Duration>,
+    pub crossing_restarts: usize,
+    pub seed: u64,
+    pub exact_crossings: bool,
+    pub exact_crossing_threshold: usize,
+    pub coordinate_combination: CoordinateCombination,
+    pub coordinate_assignment: CoordinateAssignment,
+    pub straighten_passes: usize,
+    pub edge_routing: EdgeRouting,
 }
 
 impl Config {
@@ -107,6 +185,80 @@ impl Config {
 
         read_env!(self.transpose, parse_bool, ENV_TRANSPOSE);
 
+        read_env!(self.cycle_removal, (TryFrom::try_from), ENV_CYCLE_REMOVAL);
+
+        read_env!(self.balance_ranks, parse_bool, ENV_BALANCE_RANKS);
+
+        read_env!(
+            self.balance_coordinates,
+            parse_bool,
+            ENV_BALANCE_COORDINATES
+        );
+
+        read_env!(self.search_size, (|x| x.parse::<usize>()), ENV_SEARCH_SIZE);
+
+        read_env!(
+            self.max_simplex_iterations,
+            (|x| x.parse::<usize>()),
+            ENV_MAX_SIMPLEX_ITERATIONS
+        );
+
+        read_env!(self.max_sweeps, (|x| x.parse::<usize>()), ENV_MAX_SWEEPS);
+
+        read_env!(
+            self.sweep_patience,
+            (|x| x.parse::<usize>()),
+            ENV_SWEEP_PATIENCE
+        );
+
+        read_env!(
+            self.transpose_passes,
+            (|x| x.parse::<usize>()),
+            ENV_TRANSPOSE_PASSES
+        );
+
+        read_env!(
+            self.crossing_time_budget,
+            (|x| x.parse::<u64>().map(|ms| Some(Duration::from_millis(ms)))),
+            ENV_CROSSING_TIME_BUDGET
+        );
+
+        read_env!(
+            self.crossing_restarts,
+            (|x| x.parse::<usize>()),
+            ENV_CROSSING_RESTARTS
+        );
+
+        read_env!(self.seed, (|x| x.parse::<u64>()), ENV_SEED);
+
+        read_env!(self.exact_crossings, parse_bool, ENV_EXACT_CROSSINGS);
+
+        read_env!(
+            self.exact_crossing_threshold,
+            (|x| x.parse::<usize>()),
+            ENV_EXACT_CROSSING_THRESHOLD
+        );
+
+        read_env!(
+            self.coordinate_combination,
+            (TryFrom::try_from),
+            ENV_COORDINATE_COMBINATION
+        );
+
+        read_env!(
+            self.coordinate_assignment,
+            (TryFrom::try_from),
+            ENV_COORDINATE_ASSIGNMENT
+        );
+
+        read_env!(
+            self.straighten_passes,
+            (|x| x.parse::<usize>()),
+            ENV_STRAIGHTEN_PASSES
+        );
+
+        read_env!(self.edge_routing, (TryFrom::try_from), ENV_EDGE_ROUTING);
+
         self
     }
 }
@@ -121,6 +273,23 @@ impl Default for Config {
             c_minimization: C_MINIMIZATION_DEFAULT,
             transpose: TRANSPOSE_DEFAULT,
             dummy_size: DUMMY_SIZE_DEFAULT,
+            cycle_removal: CYCLE_REMOVAL_DEFAULT,
+            balance_ranks: BALANCE_RANKS_DEFAULT,
+            balance_coordinates: BALANCE_COORDINATES_DEFAULT,
+            search_size: SEARCH_SIZE_DEFAULT,
+            max_simplex_iterations: MAX_SIMPLEX_ITERATIONS_DEFAULT,
+            max_sweeps: MAX_SWEEPS_DEFAULT,
+            sweep_patience: SWEEP_PATIENCE_DEFAULT,
+            transpose_passes: TRANSPOSE_PASSES_DEFAULT,
+            crossing_time_budget: CROSSING_TIME_BUDGET_DEFAULT,
+            crossing_restarts: CROSSING_RESTARTS_DEFAULT,
+            seed: SEED_DEFAULT,
+            exact_crossings: EXACT_CROSSINGS_DEFAULT,
+            exact_crossing_threshold: EXACT_CROSSING_THRESHOLD_DEFAULT,
+            coordinate_combination: COORDINATE_COMBINATION_DEFAULT,
+            coordinate_assignment: COORDINATE_ASSIGNMENT_DEFAULT,
+            straighten_passes: STRAIGHTEN_PASSES_DEFAULT,
+            edge_routing: EDGE_ROUTING_DEFAULT,
         }
     }
 }
@@ -136,6 +305,12 @@ pub enum RankingType {
     Up,
     /// Move vertices as far down as possible
     Down,
+    /// Move vertices as far up as possible, then move vertices down again if
+    /// this reduces the number of dummy vertices
+    NodePromotion,
+    /// Place at most `max_width` vertices on each layer, using the algorithm
+    /// of Coffman and Graham. Dummy vertices are not counted.
+    CoffmanGraham { max_width: usize },
 }
 
 impl TryFrom<String> for RankingType {
@@ -147,11 +322,21 @@ impl TryFrom<String> for RankingType {
             "minimize" => Ok(Self::MinimizeEdgeLength),
             "up" => Ok(Self::Up),
             "down" => Ok(Self::Down),
-            s => Err(format!("invalid value for ranking type: {s}")),
+            "promote" => Ok(Self::NodePromotion),
+            s => match s
+                .strip_prefix("coffman_graham:")
+                .map(|w| w.parse::<usize>())
+            {
+                Some(Ok(max_width)) if max_width > 0 => Ok(Self::CoffmanGraham { max_width }),
+                _ => Err(format!("invalid value for ranking type: {s}")),
+            },
         }
     }
 }
 
+/// Returns the name of the ranking type, without the maximum width of
+/// [RankingType::CoffmanGraham]. Use [Display] for a value which can be parsed
+/// again.
 impl From<RankingType> for &'static str {
     fn from(value: RankingType) -> Self {
         match value {
@@ -159,6 +344,19 @@ impl From<RankingType> for &'static str {
             RankingType::Down => "down",
             RankingType::Original => "original",
             RankingType::MinimizeEdgeLength => "minimize",
+            RankingType::NodePromotion => "promote",
+            RankingType::CoffmanGraham { .. } => "coffman_graham",
+        }
+    }
+}
+
+/// Writes the ranking type as it is read by its `TryFrom<String>`
+/// implementation, e.g. `coffman_graham:4`.
+impl Display for RankingType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CoffmanGraham { max_width } => write!(f, "coffman_graham:{max_width}"),
+            r => f.write_str((*r).into()),
         }
     }
 }
@@ -172,6 +370,8 @@ pub enum CrossingMinimization {
     Barycenter,
     /// Calculates the weighted median of the positions of adjacent neighbors
     Median,
+    /// Moves each vertex to the position in its layer with the fewest crossings
+    Sifting,
 }
 
 impl TryFrom<String> for CrossingMinimization {
@@ -181,6 +381,7 @@ impl TryFrom<String> for CrossingMinimization {
         match value.as_str() {
             "barycenter" => Ok(Self::Barycenter),
             "median" => Ok(Self::Median),
+            "sifting" => Ok(Self::Sifting),
             s => Err(format!("invalid value for crossing minimization: {s}")),
         }
     }
@@ -191,10 +392,217 @@ impl From<CrossingMinimization> for &'static str {
         match value {
             CrossingMinimization::Median => "median",
             CrossingMinimization::Barycenter => "barycenter",
+            CrossingMinimization::Sifting => "sifting",
         }
     }
 }
 
+/// Defines how cycles are removed from the graph, i.e. which edges are
+/// reversed. Only edges inside of strongly connected components are affected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CycleRemoval {
+    /// Uses the greedy heuristic of petgraph, which ignores edge weights
+    Greedy,
+    /// Reverses the back edges of a depth first search, which visits vertices
+    /// and edges in the order they where inserted
+    DepthFirstSearch,
+    /// Uses the heuristic of Eades, Lin and Smyth, taking edge weights into account
+    WeightedGreedy,
+    /// Finds the set of edges with the minimum total weight. Components with
+    /// more than 16 vertices fall back to [CycleRemoval::WeightedGreedy]
+    Exact,
+}
+
+impl TryFrom<String> for CycleRemoval {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "greedy" => Ok(Self::Greedy),
+            "dfs" => Ok(Self::DepthFirstSearch),
+            "weighted" => Ok(Self::WeightedGreedy),
+            "exact" => Ok(Self::Exact),
+            s => Err(format!("invalid value for cycle removal: {s}")),
+        }
+    }
+}
+
+impl From<CycleRemoval> for &'static str {
+    fn from(value: CycleRemoval) -> Self {
+        match value {
+            CycleRemoval::Greedy => "greedy",
+            CycleRemoval::DepthFirstSearch => "dfs",
+            CycleRemoval::WeightedGreedy => "weighted",
+            CycleRemoval::Exact => "exact",
+        }
+    }
+}
+
+/// Represents the vertical direction in which the coordinate assignment is run
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VDir {
+    Up,
+    Down,
+}
+
+/// Represents the horizontal direction in which the coordinate assignment is run
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HDir {
+    Left,
+    Right,
+}
+
+/// Defines how the coordinates are calculated from the four layouts of the
+/// coordinate assignment by Brandes and Koepf, one for each combination of
+/// [VDir] and [HDir]. The layouts are aligned to the one with the smallest width first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoordinateCombination {
+    /// Average of all four coordinates of a vertex
+    Average,
+    /// Average of the two median coordinates of a vertex, as described in the paper
+    AverageMedian,
+    /// Uses only the layout with the smallest width
+    MinimumWidth,
+    /// Uses only the layout of the given direction
+    Direction(VDir, HDir),
+}
+
+impl TryFrom<String> for CoordinateCombination {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "average" => Ok(Self::Average),
+            "median" => Ok(Self::AverageMedian),
+            "min_width" => Ok(Self::MinimumWidth),
+            "up_left" => Ok(Self::Direction(VDir::Up, HDir::Left)),
+            "up_right" => Ok(Self::Direction(VDir::Up, HDir::Right)),
+            "down_left" => Ok(Self::Direction(VDir::Down, HDir::Left)),
+            "down_right" => Ok(Self::Direction(VDir::Down, HDir::Right)),
+            s => Err(format!("invalid value for coordinate combination: {s}")),
+        }
+    }
+}
+
+impl From<CoordinateCombination> for &'static str {
+    fn from(value: CoordinateCombination) -> Self {
+        match value {
+            CoordinateCombination::Average => "average",
+            CoordinateCombination::AverageMedian => "median",
+            CoordinateCombination::MinimumWidth => "min_width",
+            CoordinateCombination::Direction(VDir::Up, HDir::Left) => "up_left",
+            CoordinateCombination::Direction(VDir::Up, HDir::Right) => "up_right",
+            CoordinateCombination::Direction(VDir::Down, HDir::Left) => "down_left",
+            CoordinateCombination::Direction(VDir::Down, HDir::Right) => "down_right",
+        }
+    }
+}
+
+/// Defines how the x-coordinates of the vertices are calculated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoordinateAssignment {
+    /// Combines four layouts, in which vertices are aligned with their upper
+    /// or lower neighbors, as described by Brandes and Koepf. Runs in linear time.
+    BrandesKoepf,
+    /// Minimizes the weighted horizontal length of all edges via network simplex,
+    /// as described by Gansner et al. Produces more compact layouts with fewer
+    /// bends, but is slower on large graphs.
+    NetworkSimplex,
+}
+
+impl TryFrom<String> for CoordinateAssignment {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "brandes_koepf" => Ok(Self::BrandesKoepf),
+            "network_simplex" => Ok(Self::NetworkSimplex),
+            s => Err(format!("invalid value for coordinate assignment: {s}")),
+        }
+    }
+}
+
+impl From<CoordinateAssignment> for &'static str {
+    fn from(value: CoordinateAssignment) -> Self {
+        match value {
+            CoordinateAssignment::BrandesKoepf => "brandes_koepf",
+            CoordinateAssignment::NetworkSimplex => "network_simplex",
+        }
+    }
+}
+
+/// Defines how the routes of the edges in a [Drawing] are drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeRouting {
+    /// Straight lines through the dummy vertices of long edges
+    Polyline,
+    /// Horizontal and vertical segments only. The horizontal segments are
+    /// placed on tracks between the layers, which are moved further apart if
+    /// more tracks are needed
+    Orthogonal,
+    /// Cubic bezier curves through the dummy vertices of long edges, which
+    /// leave and enter the vertices vertically. The points of an
+    /// [crate::EdgeRoute] are the control points of the curves
+    Spline,
+}
+
+impl TryFrom<String> for EdgeRouting {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "polyline" => Ok(Self::Polyline),
+            "orthogonal" => Ok(Self::Orthogonal),
+            "spline" => Ok(Self::Spline),
+            s => Err(format!("invalid value for edge routing: {s}")),
+        }
+    }
+}
+
+impl From<EdgeRouting> for &'static str {
+    fn from(value: EdgeRouting) -> Self {
+        match value {
+            EdgeRouting::Polyline => "polyline",
+            EdgeRouting::Orthogonal => "orthogonal",
+            EdgeRouting::Spline => "spline",
+        }
+    }
+}
+
+/// Weights of the quality function which the automatic configuration uses to
+/// compare layouts, see [CoordinatesBuilder::build_auto].
+///
+/// The score of a layout is the weighted sum of the number of crossings, the
+/// area in units of the squared vertex spacing and the total edge length in
+/// ranks, summed up over all connected components. The layout with the lowest
+/// score wins.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QualityWeights {
+    pub crossings: f64,
+    pub area: f64,
+    pub edge_length: f64,
+}
+
+impl Default for QualityWeights {
+    fn default() -> Self {
+        Self {
+            crossings: 1.0,
+            area: 0.1,
+            edge_length: 0.1,
+        }
+    }
+}
+
+impl QualityWeights {
+    /// Returns the score of the metrics of a layout, see [QualityWeights].
+    pub fn score(&self, metrics: &Metrics, vertex_spacing: usize) -> f64 {
+        let cell = vertex_spacing.max(1).pow(2) as f64;
+        self.crossings * metrics.crossings as f64
+            + self.area * metrics.area() as f64 / cell
+            + self.edge_length * metrics.total_edge_length as f64
+    }
+}
+
 /// Can be used to configure the layout of the graph, via the builder pattern.
 ///
 /// # Example
@@ -210,6 +618,7 @@ impl From<CrossingMinimization> for &'static str {
 pub struct CoordinatesBuilder<Input: IntoCoordinates> {
     config: Config,
     _inner: StableDiGraph<Vertex, Edge>,
+    control: Control,
     pd: PhantomData<Input>,
 }
 
@@ -218,6 +627,7 @@ impl<Input: IntoCoordinates> CoordinatesBuilder<Input> {
         Self {
             config: Config::default(),
             _inner: graph,
+            control: Control::default(),
             pd: PhantomData,
         }
     }
@@ -278,6 +688,176 @@ impl<Input: IntoCoordinates> CoordinatesBuilder<Input> {
         self
     }
 
+    /// Set the strategy used to remove cycles, see [Config]
+    pub fn cycle_removal(mut self, v: CycleRemoval) -> Self {
+        trace!(target: "initializing",
+            "Strategy for cycle removal: {v:?}");
+        self.config.cycle_removal = v;
+        self
+    }
+
+    /// Balance the number of vertices in each rank, see [Config]
+    pub fn balance_ranks(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Balance ranks: {v}");
+        self.config.balance_ranks = v;
+        self
+    }
+
+    /// Center vertices between their neighbors, see [Config]
+    pub fn balance_coordinates(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Balance coordinates: {v}");
+        self.config.balance_coordinates = v;
+        self
+    }
+
+    /// Set the number of candidates compared in the network simplex, see [Config]
+    pub fn search_size(mut self, v: usize) -> Self {
+        trace!(target: "initializing",
+            "Search size of network simplex: {v}");
+        self.config.search_size = v;
+        self
+    }
+
+    /// Set the maximum number of iterations of the network simplex, see [Config]
+    pub fn max_simplex_iterations(mut self, v: usize) -> Self {
+        trace!(target: "initializing",
+            "Maximum iterations of network simplex: {v}");
+        self.config.max_simplex_iterations = v;
+        self
+    }
+
+    /// Set the maximum number of sweeps during crossing minimization, see [Config]
+    pub fn max_sweeps(mut self, v: usize) -> Self {
+        trace!(target: "initializing",
+            "Maximum number of sweeps: {v}");
+        self.config.max_sweeps = v;
+        self
+    }
+
+    /// Set the number of sweeps without improvement, after which crossing minimization stops, see [Config]
+    pub fn sweep_patience(mut self, v: usize) -> Self {
+        trace!(target: "initializing",
+            "Sweeps without improvement before stopping: {v}");
+        self.config.sweep_patience = v;
+        self
+    }
+
+    /// Set the maximum number of transpose passes after each sweep, see [Config]
+    pub fn transpose_passes(mut self, v: usize) -> Self {
+        trace!(target: "initializing",
+            "Maximum number of transpose passes: {v}");
+        self.config.transpose_passes = v;
+        self
+    }
+
+    /// Set the time after which crossing minimization stops, see [Config]
+    pub fn crossing_time_budget(mut self, v: Duration) -> Self {
+        trace!(target: "initializing",
+            "Time budget for crossing minimization: {v:?}");
+        self.config.crossing_time_budget = Some(v);
+        self
+    }
+
+    /// Set the number of restarts of crossing minimization, see [Config]
+    pub fn crossing_restarts(mut self, v: usize) -> Self {
+        trace!(target: "initializing",
+            "Restarts of crossing minimization: {v}");
+        self.config.crossing_restarts = v;
+        self
+    }
+
+    /// Set the seed for random initial orders during crossing minimization, see [Config]
+    pub fn seed(mut self, v: u64) -> Self {
+        trace!(target: "initializing",
+            "Seed: {v}");
+        self.config.seed = v;
+        self
+    }
+
+    /// Set if crossings are minimized exactly for small graphs, see [Config]
+    pub fn exact_crossings(mut self, v: bool) -> Self {
+        trace!(target: "initializing",
+            "Exact crossing minimization: {v}");
+        self.config.exact_crossings = v;
+        self
+    }
+
+    /// Set the maximum number of vertices in two adjacent layers for exact crossing minimization, see [Config]
+    pub fn exact_crossing_threshold(mut self, v: usize) -> Self {
+        trace!(target: "initializing",
+            "Threshold for exact crossing minimization: {v}");
+        self.config.exact_crossing_threshold = v;
+        self
+    }
+
+    /// Set how the layouts of the coordinate assignment are combined, see [Config]
+    pub fn coordinate_combination(mut self, v: CoordinateCombination) -> Self {
+        trace!(target: "initializing",
+            "Combination of layouts: {v:?}");
+        self.config.coordinate_combination = v;
+        self
+    }
+
+    /// Set the algorithm of the coordinate assignment, see [Config]
+    pub fn coordinate_assignment(mut self, v: CoordinateAssignment) -> Self {
+        trace!(target: "initializing",
+            "Coordinate assignment: {v:?}");
+        self.config.coordinate_assignment = v;
+        self
+    }
+
+    /// Set the maximum number of straightening passes, see [Config]
+    pub fn straighten_passes(mut self, v: usize) -> Self {
+        trace!(target: "initializing",
+            "Straightening passes: {v}");
+        self.config.straighten_passes = v;
+        self
+    }
+
+    /// Set how edges are routed, see [Config]
+    pub fn edge_routing(mut self, v: EdgeRouting) -> Self {
+        trace!(target: "initializing",
+            "Edge routing: {v:?}");
+        self.config.edge_routing = v;
+        self
+    }
+
+    /// Set the shape of each vertex, which the routes of its edges are clipped
+    /// to, see [VertexShape]. `shape` is called with the index of each vertex,
+    /// i.e. its id when the layout is created from edges.
+    pub fn vertex_shapes(mut self, shape: impl Fn(usize) -> VertexShape) -> Self {
+        trace!(target: "initializing",
+            "Setting vertex shapes");
+        for v in self._inner.node_indices().collect::<Vec<_>>() {
+            self._inner[v].set_shape(shape(v.index()));
+        }
+        self
+    }
+
+    /// Set a callback which is called with the [Progress] of the build, i.e.
+    /// when a phase starts and after each iteration of the network simplex
+    /// and each sweep of the crossing reduction. With [Self::build_auto], it
+    /// is called from several threads.
+    pub fn progress(mut self, progress: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        trace!(target: "initializing",
+            "Setting progress callback");
+        self.control.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Set a token which cancels the build. It is checked between the phases
+    /// and in the loops of the network simplex, the sweeps and transpose. A
+    /// cancelled build returns no layouts, or [Cancelled] from the `try_`
+    /// variants of the build methods.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        trace!(target: "initializing",
+            "Setting cancellation token");
+        self.control.cancellation = Some(token);
+        self
+    }
+
     pub fn with_config(mut self, config: Config) -> Self {
         trace!(target: "initializing",
             "With config {:?}", config);
@@ -294,10 +874,27 @@ impl<Input: IntoCoordinates> CoordinatesBuilder<Input> {
     /// | RUST_GRAPH_MIN_LEN    | integer, > 0         | 1          | minimum edge length between layers |
     /// | RUST_GRAPH_V_SPACING  | integer, > 0         | 10         | minimum spacing between vertices on the same layer |
     /// | RUST_GRAPH_DUMMIES    | y \| n               | y          | if dummy vertices are included in the final layout |
-    /// | RUST_GRAPH_R_TYPE     | original \| minimize \| up \| down | minimize   | defines how vertices are places vertically |
-    /// | RUST_GRAPH_CROSS_MIN  | barycenter \| median | barycenter | which heuristic to use for crossing reduction |
+    /// | RUST_GRAPH_R_TYPE     | original \| minimize \| up \| down \| promote \| coffman_graham:N | minimize   | defines how vertices are places vertically, N is the maximum number of vertices per layer |
+    /// | RUST_GRAPH_CROSS_MIN  | barycenter \| median \| sifting | barycenter | which heuristic to use for crossing reduction |
     /// | RUST_GRAPH_TRANSPOSE  | y \| n               | y          | if transpose function is used to further try to reduce crossings (may increase runtime significally for large graphs) |
     /// | RUST_GRAPH_DUMMY_SIZE | float, 1 >= v > 0    | 1.0        |size of dummy vertices in final layout, if dummy vertices are included. this will squish the graph horizontally |
+    /// | RUST_GRAPH_CYCLE_REMOVAL | greedy \| dfs \| weighted \| exact | greedy | which edges are reversed to remove cycles |
+    /// | RUST_GRAPH_BALANCE_RANKS | y \| n | n | if vertices are moved to ranks with fewer vertices, when ranking type is minimize |
+    /// | RUST_GRAPH_BALANCE_COORDS | y \| n | n | if vertices are centered between their neighbors after coordinate calculation |
+    /// | RUST_GRAPH_SEARCH_SIZE | integer, > 0 | 30 | number of candidates compared before an edge is replaced in the network simplex |
+    /// | RUST_GRAPH_MAX_SIMPLEX_ITER | integer | unlimited | maximum number of iterations of the network simplex |
+    /// | RUST_GRAPH_MAX_SWEEPS | integer | unlimited | maximum number of sweeps during crossing minimization |
+    /// | RUST_GRAPH_SWEEP_PATIENCE | integer | 4 | number of sweeps without improvement, after which crossing minimization stops |
+    /// | RUST_GRAPH_TRANSPOSE_PASSES | integer | unlimited | maximum number of transpose passes after each sweep |
+    /// | RUST_GRAPH_CROSS_MIN_BUDGET | integer, milliseconds | none | time after which crossing minimization returns the best order found so far |
+    /// | RUST_GRAPH_RESTARTS | integer | 0 | number of restarts of crossing minimization from other initial orders |
+    /// | RUST_GRAPH_SEED | integer | 0 | seed for random initial orders of crossing minimization |
+    /// | RUST_GRAPH_EXACT_CROSSINGS | y \| n | n | if the order with the fewest crossings is searched exactly for small graphs |
+    /// | RUST_GRAPH_EXACT_THRESHOLD | integer | 30 | maximum number of vertices in two adjacent layers for exact crossing minimization |
+    /// | RUST_GRAPH_COORD_COMBINATION | average \| median \| min_width \| up_left \| up_right \| down_left \| down_right | average | how the layouts of the coordinate assignment are combined |
+    /// | RUST_GRAPH_COORD_ASSIGNMENT | brandes_koepf \| network_simplex | brandes_koepf | which algorithm calculates the x-coordinates |
+    /// | RUST_GRAPH_STRAIGHTEN_PASSES | integer | 0 | maximum number of passes which straighten edges after the coordinate assignment |
+    /// | RUST_GRAPH_EDGE_ROUTING | polyline \| orthogonal \| spline | polyline | how the routes of the edges are drawn |
     pub fn configure_from_env(mut self) -> Self {
         self.config = self.config.read_env();
         self
@@ -307,50 +904,171 @@ impl<Input: IntoCoordinates> CoordinatesBuilder<Input> {
 impl<V, E> CoordinatesBuilder<StableDiGraph<V, E>> {
     /// Build the layout.
     pub fn build(self) -> Layouts<NodeIndex> {
+        self.build_with_edges()
+            .into_iter()
+            .map(Drawing::into_layout)
+            .collect()
+    }
+
+    /// Build the layout, including the routes of the edges. A cancelled
+    /// build returns no layouts, see [Self::try_build_with_edges].
+    pub fn build_with_edges(self) -> Drawings<NodeIndex> {
+        self.try_build_with_edges().unwrap_or_default()
+    }
+
+    /// Build the layout, including the routes of the edges, or return
+    /// [Cancelled] if the build was cancelled via its [CancellationToken].
+    pub fn try_build_with_edges(self) -> Result<Drawings<NodeIndex>, Cancelled> {
         let Self {
             config,
             _inner: graph,
+            control,
             ..
         } = self;
-        algorithm::start(
-            graph.map(|_, _| Vertex::default(), |_, _| Edge::default()),
+        let drawings = algorithm::start(graph, config, &control)?;
+        Ok(drawings
+            .into_iter()
+            .map(|d| d.map_ids(|id| NodeIndex::from(id as u32)))
+            .collect())
+    }
+
+    /// Build the layout with several configurations and return the one with
+    /// the lowest score, together with the configuration which produced it.
+    ///
+    /// The configured ranking type, crossing minimization and transpose are
+    /// tried first, followed by all combinations of the ranking types
+    /// `MinimizeEdgeLength`, `Up`, `Down` and `NodePromotion`, all crossing
+    /// minimization heuristics and transpose on and off. All other options
+    /// are kept. The configurations are laid out in parallel, if threads are
+    /// available.
+    ///
+    /// A cancelled build returns no layouts and the configuration of the
+    /// builder, see [Self::try_build_auto].
+    pub fn build_auto(self, weights: QualityWeights) -> (Drawings<NodeIndex>, Config) {
+        let config = self.config;
+        self.try_build_auto(weights).unwrap_or((Vec::new(), config))
+    }
+
+    /// Like [Self::build_auto], but returns [Cancelled] if the build was
+    /// cancelled via its [CancellationToken].
+    pub fn try_build_auto(
+        self,
+        weights: QualityWeights,
+    ) -> Result<(Drawings<NodeIndex>, Config), Cancelled> {
+        let Self {
             config,
-        )
-        .into_iter()
-        .map(|(l, w, h)| {
-            (
-                l.into_iter()
-                    .map(|(id, coords)| (NodeIndex::from(id as u32), coords))
-                    .collect(),
-                w,
-                h,
-            )
-        })
-        .collect()
+            _inner: graph,
+            control,
+            ..
+        } = self;
+        let (drawings, config) = algorithm::start_auto(graph, config, weights, &control)?;
+        let drawings = drawings
+            .into_iter()
+            .map(|d| d.map_ids(|id| NodeIndex::from(id as u32)))
+            .collect();
+        Ok((drawings, config))
     }
 }
 
 impl CoordinatesBuilder<&[(u32, u32)]> {
     /// Build the layout.
     pub fn build(self) -> Layouts<usize> {
+        self.build_with_edges()
+            .into_iter()
+            .map(Drawing::into_layout)
+            .collect()
+    }
+
+    /// Build the layout, including the routes of the edges. A cancelled
+    /// build returns no layouts, see [Self::try_build_with_edges].
+    pub fn build_with_edges(self) -> Drawings<usize> {
+        self.try_build_with_edges().unwrap_or_default()
+    }
+
+    /// Build the layout, including the routes of the edges, or return
+    /// [Cancelled] if the build was cancelled via its [CancellationToken].
+    pub fn try_build_with_edges(self) -> Result<Drawings<usize>, Cancelled> {
+        let Self {
+            config,
+            _inner: graph,
+            control,
+            ..
+        } = self;
+        algorithm::start(graph, config, &control)
+    }
+
+    /// Build the layout with several configurations and return the best one,
+    /// together with its configuration. See `build_auto` of the builder of a
+    /// [StableDiGraph] for the configurations which are tried.
+    pub fn build_auto(self, weights: QualityWeights) -> (Drawings<usize>, Config) {
+        let config = self.config;
+        self.try_build_auto(weights).unwrap_or((Vec::new(), config))
+    }
+
+    /// Like [Self::build_auto], but returns [Cancelled] if the build was
+    /// cancelled via its [CancellationToken].
+    pub fn try_build_auto(
+        self,
+        weights: QualityWeights,
+    ) -> Result<(Drawings<usize>, Config), Cancelled> {
         let Self {
             config,
             _inner: graph,
+            control,
             ..
         } = self;
-        algorithm::start(graph, config)
+        algorithm::start_auto(graph, config, weights, &control)
     }
 }
 
 impl CoordinatesBuilder<(&[u32], &[(u32, u32)])> {
     /// Build the layout.
     pub fn build(self) -> Layouts<usize> {
+        self.build_with_edges()
+            .into_iter()
+            .map(Drawing::into_layout)
+            .collect()
+    }
+
+    /// Build the layout, including the routes of the edges. A cancelled
+    /// build returns no layouts, see [Self::try_build_with_edges].
+    pub fn build_with_edges(self) -> Drawings<usize> {
+        self.try_build_with_edges().unwrap_or_default()
+    }
+
+    /// Build the layout, including the routes of the edges, or return
+    /// [Cancelled] if the build was cancelled via its [CancellationToken].
+    pub fn try_build_with_edges(self) -> Result<Drawings<usize>, Cancelled> {
+        let Self {
+            config,
+            _inner: graph,
+            control,
+            ..
+        } = self;
+        algorithm::start(graph, config, &control)
+    }
+
+    /// Build the layout with several configurations and return the best one,
+    /// together with its configuration. See `build_auto` of the builder of a
+    /// [StableDiGraph] for the configurations which are tried.
+    pub fn build_auto(self, weights: QualityWeights) -> (Drawings<usize>, Config) {
+        let config = self.config;
+        self.try_build_auto(weights).unwrap_or((Vec::new(), config))
+    }
+
+    /// Like [Self::build_auto], but returns [Cancelled] if the build was
+    /// cancelled via its [CancellationToken].
+    pub fn try_build_auto(
+        self,
+        weights: QualityWeights,
+    ) -> Result<(Drawings<usize>, Config), Cancelled> {
         let Self {
             config,
             _inner: graph,
+            control,
             ..
         } = self;
-        algorithm::start(graph, config)
+        algorithm::start_auto(graph, config, weights, &control)
     }
 }
 
@@ -366,14 +1084,83 @@ fn from_env_all_valid() {
     env::set_var(ENV_CROSSING_MINIMIZATION, "median");
     env::set_var(ENV_TRANSPOSE, "n");
     env::set_var(ENV_VERTEX_SPACING, "20");
+    env::set_var(ENV_CYCLE_REMOVAL, "exact");
+    env::set_var(ENV_BALANCE_RANKS, "y");
+    env::set_var(ENV_BALANCE_COORDINATES, "y");
+    env::set_var(ENV_SEARCH_SIZE, "10");
+    env::set_var(ENV_MAX_SIMPLEX_ITERATIONS, "100");
+    env::set_var(ENV_MAX_SWEEPS, "8");
+    env::set_var(ENV_SWEEP_PATIENCE, "2");
+    env::set_var(ENV_TRANSPOSE_PASSES, "1");
+    env::set_var(ENV_CROSSING_TIME_BUDGET, "500");
+    env::set_var(ENV_CROSSING_RESTARTS, "3");
+    env::set_var(ENV_SEED, "42");
+    env::set_var(ENV_EXACT_CROSSINGS, "y");
+    env::set_var(ENV_EXACT_CROSSING_THRESHOLD, "12");
+    env::set_var(ENV_COORDINATE_COMBINATION, "up_left");
+    env::set_var(ENV_COORDINATE_ASSIGNMENT, "network_simplex");
+    env::set_var(ENV_STRAIGHTEN_PASSES, "10");
+    env::set_var(ENV_EDGE_ROUTING, "orthogonal");
     let cfg = from_edges(&edges).configure_from_env();
     assert_eq!(cfg.config.minimum_length, 5);
-    assert_eq!(cfg.config.dummy_vertices, true);
+    assert!(cfg.config.dummy_vertices);
     assert_eq!(cfg.config.dummy_size, 0.1);
     assert_eq!(cfg.config.ranking_type, RankingType::Up);
     assert_eq!(cfg.config.c_minimization, CrossingMinimization::Median);
-    assert_eq!(cfg.config.transpose, false);
+    assert!(!cfg.config.transpose);
     assert_eq!(cfg.config.vertex_spacing, 20);
+    assert_eq!(cfg.config.cycle_removal, CycleRemoval::Exact);
+    assert!(cfg.config.balance_ranks);
+    assert!(cfg.config.balance_coordinates);
+    assert_eq!(cfg.config.search_size, 10);
+    assert_eq!(cfg.config.max_simplex_iterations, 100);
+    assert_eq!(cfg.config.max_sweeps, 8);
+    assert_eq!(cfg.config.sweep_patience, 2);
+    assert_eq!(cfg.config.transpose_passes, 1);
+    assert_eq!(
+        cfg.config.crossing_time_budget,
+        Some(Duration::from_millis(500))
+    );
+    assert_eq!(cfg.config.crossing_restarts, 3);
+    assert_eq!(cfg.config.seed, 42);
+    assert!(cfg.config.exact_crossings);
+    assert_eq!(cfg.config.exact_crossing_threshold, 12);
+    assert_eq!(
+        cfg.config.coordinate_combination,
+        CoordinateCombination::Direction(VDir::Up, HDir::Left)
+    );
+    assert_eq!(
+        cfg.config.coordinate_assignment,
+        CoordinateAssignment::NetworkSimplex
+    );
+    assert_eq!(cfg.config.straighten_passes, 10);
+    assert_eq!(cfg.config.edge_routing, EdgeRouting::Orthogonal);
+}
+
+#[test]
+fn ranking_type_coffman_graham_from_string() {
+    assert_eq!(
+        RankingType::try_from("coffman_graham:4".to_string()),
+        Ok(RankingType::CoffmanGraham { max_width: 4 })
+    );
+    assert!(RankingType::try_from("coffman_graham:0".to_string()).is_err());
+}
+
+#[test]
+fn ranking_type_round_trip() {
+    for ranking_type in [
+        RankingType::Original,
+        RankingType::MinimizeEdgeLength,
+        RankingType::Up,
+        RankingType::Down,
+        RankingType::NodePromotion,
+        RankingType::CoffmanGraham { max_width: 3 },
+    ] {
+        assert_eq!(
+            RankingType::try_from(ranking_type.to_string()),
+            Ok(ranking_type)
+        );
+    }
 }
 
 #[test]
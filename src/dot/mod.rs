@@ -0,0 +1,129 @@
+//! Support for graphs in the Graphviz DOT language.
+//!
+//! This module is only available if the `dot` feature is enabled.
+//!
+//! [parse] reads a DOT document into a [DotGraph], which contains the input
+//! graph together with a [Config] derived from the attributes of the
+//! document. Attributes that have an equivalent in this crate are mapped onto
+//! the configuration, the ones that would influence the layout in Graphviz
+//! but are not supported are reported as warnings.
+//!
+//! [to_dot] writes a computed layout back as DOT, including the positions of
+//! the vertices and the routes of the edges.
+use std::fmt::Display;
+
+use petgraph::stable_graph::StableDiGraph;
+
+use crate::{
+    algorithm::{Edge, Vertex},
+    configure::{Config, CoordinatesBuilder},
+    VertexShape,
+};
+
+mod parser;
+mod writer;
+
+pub use parser::{parse, ParseError};
+pub use writer::to_dot;
+
+/// A graph read from a DOT document.
+#[derive(Clone, Debug)]
+pub struct DotGraph {
+    /// The name of the graph, if it has one
+    pub name: Option<String>,
+    /// The vertices and edges of the document. Undirected edges are
+    /// directed from the left to the right operand.
+    pub graph: StableDiGraph<DotNode, DotEdge>,
+    /// The configuration derived from the attributes of the document
+    pub config: Config,
+    /// Messages about attributes and statements which were ignored
+    pub warnings: Vec<String>,
+}
+
+impl DotGraph {
+    /// Returns a [CoordinatesBuilder] for the graph, which is configured with
+    /// the attributes of the document and uses the edge weights and the
+    /// shapes of the vertices.
+    pub fn builder(&self) -> CoordinatesBuilder<StableDiGraph<DotNode, DotEdge>> {
+        let graph = self.graph.map(
+            |id, v| {
+                let mut vertex = Vertex::new(id.index());
+                vertex.set_shape(v.shape);
+                vertex
+            },
+            |_, e| Edge::new(e.weight),
+        );
+        CoordinatesBuilder::new(graph).with_config(self.config)
+    }
+}
+
+/// A vertex of a DOT document.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DotNode {
+    /// The identifier of the vertex in the document
+    pub name: String,
+    /// All attributes of the vertex, including the ones inherited from
+    /// `node [...]` statements
+    pub attributes: Vec<(String, String)>,
+    /// The shape of the vertex, read from the attributes `shape`, `style`,
+    /// `width` and `height`. If none of them is set, the vertex has no size.
+    /// Otherwise the defaults of Graphviz are used for missing attributes,
+    /// i.e. an ellipse of 0.75 by 0.5 inches.
+    pub shape: VertexShape,
+}
+
+impl DotNode {
+    /// Returns the value of the attribute `key`, if it is set.
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        get_attribute(&self.attributes, key)
+    }
+}
+
+impl Display for DotNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.attribute("label") {
+            Some(label) if label != "\\N" => f.write_str(label),
+            _ => f.write_str(&self.name),
+        }
+    }
+}
+
+/// An edge of a DOT document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DotEdge {
+    /// The weight of the edge, read from the `weight` attribute
+    pub weight: i32,
+    /// All attributes of the edge, including the ones inherited from
+    /// `edge [...]` statements
+    pub attributes: Vec<(String, String)>,
+}
+
+impl DotEdge {
+    /// Returns the value of the attribute `key`, if it is set.
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        get_attribute(&self.attributes, key)
+    }
+}
+
+impl Default for DotEdge {
+    fn default() -> Self {
+        Self {
+            weight: 1,
+            attributes: Vec::new(),
+        }
+    }
+}
+
+impl Display for DotEdge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.attribute("label").unwrap_or_default())
+    }
+}
+
+fn get_attribute<'a>(attributes: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .rev()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
@@ -0,0 +1,981 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use log::{debug, info, warn};
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+use crate::configure::{Config, EdgeRouting};
+use crate::{Shape, VertexShape};
+
+use super::{get_attribute, DotEdge, DotGraph, DotNode};
+
+/// Graph attributes which influence the layout in Graphviz, but have no
+/// equivalent in this crate.
+static IGNORED_GRAPH_ATTRIBUTES: [&str; 11] = [
+    "rank",
+    "ranksep",
+    "newrank",
+    "ordering",
+    "size",
+    "ratio",
+    "compound",
+    "clusterrank",
+    "mclimit",
+    "nslimit",
+    "searchsize",
+];
+
+/// Vertex attributes which influence the layout in Graphviz, but have no
+/// equivalent in this crate.
+static IGNORED_NODE_ATTRIBUTES: [&str; 4] = ["fixedsize", "group", "pos", "pin"];
+
+/// Edge attributes which influence the layout in Graphviz, but have no
+/// equivalent in this crate.
+static IGNORED_EDGE_ATTRIBUTES: [&str; 7] = [
+    "constraint",
+    "headport",
+    "tailport",
+    "samehead",
+    "sametail",
+    "lhead",
+    "ltail",
+];
+
+/// Space between the borders of vertices in points, the default `nodesep` of
+/// Graphviz
+static NODESEP_DEFAULT: usize = 18;
+
+/// Error returned if a DOT document can't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line in which the error occurred, starting at 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses a graph written in the DOT language.
+///
+/// The following attributes are mapped onto the [Config] of the returned graph:
+///
+/// | attribute | mapped to |
+/// | --------- | --------- |
+/// | `nodesep` (graph) | `vertex_spacing`, converted from inches to points. Like in Graphviz it is the space between the borders of the vertices, so the size of the largest vertex is added. If it isn't set, 0.25 inches are used for graphs with vertex sizes |
+/// | `shape`, `width` and `height` (vertex) | [DotNode::shape], converted from inches to points |
+/// | `splines` (graph) | `edge_routing`: `ortho` is orthogonal, `spline` and `true` are splines, `polyline`, `line` and `false` are polylines |
+/// | `minlen` (edge)   | `minimum_length`, the maximum is used if edges differ |
+/// | `weight` (edge)   | weight of the edge |
+///
+/// Attributes like `rankdir`, `ranksep`, `rank=same` or clusters
+/// (`subgraph cluster_*`) are not supported. They are
+/// still contained in the attributes of the graph, but a warning is logged
+/// and added to [DotGraph::warnings]. Self loops are removed.
+///
+/// # Example
+/// ```
+/// let dot = rust_sugiyama::dot::parse("digraph { a -> b; a -> c [weight=2]; }").unwrap();
+/// let layouts = dot.builder().build();
+/// assert_eq!(layouts[0].0.len(), 3);
+/// ```
+pub fn parse(input: &str) -> Result<DotGraph, ParseError> {
+    info!(target: "dot", "Parsing DOT document of {} bytes", input.len());
+    let tokens = tokenize(input)?;
+    debug!(target: "dot", "Read {} tokens", tokens.len());
+    Parser::new(tokens).parse()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    /// An identifier, the flag denotes whether it was quoted
+    Id(String, bool),
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Equals,
+    Semicolon,
+    Comma,
+    Colon,
+    EdgeOp,
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Id(id, false) if id.eq_ignore_ascii_case(keyword))
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let chars = input.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\n' => {
+                line += 1;
+                i += 1;
+            }
+            c if c.is_whitespace() => i += 1,
+            // preprocessor output and line comments
+            '#' => skip_line(&chars, &mut i),
+            '/' if next == Some('/') => skip_line(&chars, &mut i),
+            '/' if next == Some('*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    if chars[i] == '\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err(ParseError::new(line, "unterminated comment"));
+                }
+                i += 2;
+            }
+            '{' | '}' | '[' | ']' | '=' | ';' | ',' | ':' => {
+                let token = match c {
+                    '{' => Token::LBrace,
+                    '}' => Token::RBrace,
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    '=' => Token::Equals,
+                    ';' => Token::Semicolon,
+                    ',' => Token::Comma,
+                    _ => Token::Colon,
+                };
+                tokens.push((token, line));
+                i += 1;
+            }
+            '-' if next == Some('>') || next == Some('-') => {
+                tokens.push((Token::EdgeOp, line));
+                i += 2;
+            }
+            '"' => {
+                let start_line = line;
+                let mut value = read_quoted(&chars, &mut i, &mut line)?;
+                // quoted strings may be concatenated with '+'
+                loop {
+                    let mut j = i;
+                    let mut lines = 0;
+                    while j < chars.len() && chars[j].is_whitespace() {
+                        if chars[j] == '\n' {
+                            lines += 1;
+                        }
+                        j += 1;
+                    }
+                    if chars.get(j) != Some(&'+') {
+                        break;
+                    }
+                    j += 1;
+                    while j < chars.len() && chars[j].is_whitespace() {
+                        if chars[j] == '\n' {
+                            lines += 1;
+                        }
+                        j += 1;
+                    }
+                    if chars.get(j) != Some(&'"') {
+                        break;
+                    }
+                    i = j;
+                    line += lines;
+                    value.push_str(&read_quoted(&chars, &mut i, &mut line)?);
+                }
+                tokens.push((Token::Id(value, true), start_line));
+            }
+            '<' => {
+                let start_line = line;
+                let mut depth = 0;
+                let start = i + 1;
+                loop {
+                    match chars.get(i) {
+                        Some('<') => depth += 1,
+                        Some('>') => depth -= 1,
+                        Some('\n') => line += 1,
+                        Some(_) => (),
+                        None => {
+                            return Err(ParseError::new(start_line, "unterminated HTML string"))
+                        }
+                    }
+                    i += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                let value = chars[start..i - 1].iter().collect();
+                tokens.push((Token::Id(value, true), start_line));
+            }
+            c if c.is_ascii_digit() || c == '.' || c == '-' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let value = chars[start..i].iter().collect::<String>();
+                if value.parse::<f64>().is_err() {
+                    return Err(ParseError::new(line, format!("invalid number: {value}")));
+                }
+                tokens.push((Token::Id(value, false), line));
+            }
+            c if c.is_alphabetic() || c == '_' || !c.is_ascii() => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || chars[i] == '_' || !chars[i].is_ascii())
+                {
+                    i += 1;
+                }
+                tokens.push((Token::Id(chars[start..i].iter().collect(), false), line));
+            }
+            c => {
+                return Err(ParseError::new(
+                    line,
+                    format!("unexpected character: {c:?}"),
+                ))
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn skip_line(chars: &[char], i: &mut usize) {
+    while *i < chars.len() && chars[*i] != '\n' {
+        *i += 1;
+    }
+}
+
+fn read_quoted(chars: &[char], i: &mut usize, line: &mut usize) -> Result<String, ParseError> {
+    let start_line = *line;
+    let mut value = String::new();
+    // skip opening quote
+    *i += 1;
+    loop {
+        match chars.get(*i) {
+            Some('"') => break,
+            Some('\\') if chars.get(*i + 1) == Some(&'"') => {
+                value.push('"');
+                *i += 1;
+            }
+            // line continuation
+            Some('\\') if chars.get(*i + 1) == Some(&'\n') => {
+                *line += 1;
+                *i += 1;
+            }
+            Some(c) => {
+                if *c == '\n' {
+                    *line += 1;
+                }
+                value.push(*c);
+            }
+            None => return Err(ParseError::new(start_line, "unterminated string")),
+        }
+        *i += 1;
+    }
+    // skip closing quote
+    *i += 1;
+    Ok(value)
+}
+
+/// Attributes set via `node [...]` and `edge [...]`, which are inherited by
+/// subgraphs.
+#[derive(Clone, Default)]
+struct Scope {
+    node: Vec<(String, String)>,
+    edge: Vec<(String, String)>,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    graph: StableDiGraph<DotNode, DotEdge>,
+    ids: HashMap<String, NodeIndex>,
+    graph_attributes: Vec<(String, String)>,
+    /// Vertices referenced in each currently open subgraph
+    members: Vec<Vec<NodeIndex>>,
+    warnings: Vec<String>,
+    warned: HashSet<String>,
+}
+
+impl Parser {
+    fn new(tokens: Vec<(Token, usize)>) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            graph: StableDiGraph::new(),
+            ids: HashMap::new(),
+            graph_attributes: Vec::new(),
+            members: Vec::new(),
+            warnings: Vec::new(),
+            warned: HashSet::new(),
+        }
+    }
+
+    fn parse(mut self) -> Result<DotGraph, ParseError> {
+        if self.peek().is_some_and(|t| t.is_keyword("strict")) {
+            self.pos += 1;
+        }
+        match self.next() {
+            Some(t) if t.is_keyword("digraph") || t.is_keyword("graph") => (),
+            _ => return Err(self.error("expected 'graph' or 'digraph'")),
+        }
+        let name = match self.peek() {
+            Some(Token::Id(id, _)) => {
+                let id = id.clone();
+                self.pos += 1;
+                Some(id)
+            }
+            _ => None,
+        };
+        self.expect(Token::LBrace)?;
+        self.parse_stmt_list(&mut Scope::default(), true)?;
+        self.expect(Token::RBrace)?;
+        if self.peek().is_some() {
+            return Err(self.error("unexpected content after end of graph"));
+        }
+        self.read_shapes();
+        let config = self.create_config();
+        Ok(DotGraph {
+            name,
+            graph: self.graph,
+            config,
+            warnings: self.warnings,
+        })
+    }
+
+    fn parse_stmt_list(&mut self, scope: &mut Scope, is_root: bool) -> Result<(), ParseError> {
+        while let Some(token) = self.peek() {
+            if *token == Token::RBrace {
+                break;
+            }
+            self.parse_stmt(scope, is_root)?;
+            if self.peek() == Some(&Token::Semicolon) {
+                self.pos += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_stmt(&mut self, scope: &mut Scope, is_root: bool) -> Result<(), ParseError> {
+        let token = self.peek().cloned();
+        let is_attr_stmt = self.tokens.get(self.pos + 1).map(|t| &t.0) == Some(&Token::LBracket);
+        match token {
+            Some(t) if is_attr_stmt && t.is_keyword("graph") => {
+                self.pos += 1;
+                let attributes = self.parse_attr_list()?;
+                self.set_graph_attributes(attributes, is_root);
+            }
+            Some(t) if is_attr_stmt && t.is_keyword("node") => {
+                self.pos += 1;
+                let attributes = self.parse_attr_list()?;
+                scope.node.extend(attributes);
+            }
+            Some(t) if is_attr_stmt && t.is_keyword("edge") => {
+                self.pos += 1;
+                let attributes = self.parse_attr_list()?;
+                scope.edge.extend(attributes);
+            }
+            Some(Token::LBrace) => {
+                let operand = self.parse_subgraph(scope)?;
+                self.parse_edge_stmt(operand, scope)?;
+            }
+            Some(t) if t.is_keyword("subgraph") => {
+                let operand = self.parse_subgraph(scope)?;
+                self.parse_edge_stmt(operand, scope)?;
+            }
+            Some(Token::Id(id, _))
+                if self.tokens.get(self.pos + 1).map(|t| &t.0) == Some(&Token::Equals) =>
+            {
+                self.pos += 2;
+                let value = self.parse_id()?;
+                self.set_graph_attributes(vec![(id, value)], is_root);
+            }
+            Some(Token::Id(..)) => {
+                let vertex = self.parse_node_id(scope)?;
+                if self.peek() == Some(&Token::EdgeOp) {
+                    self.parse_edge_stmt(vec![vertex], scope)?;
+                } else if self.peek() == Some(&Token::LBracket) {
+                    let attributes = self.parse_attr_list()?;
+                    self.graph[vertex].attributes.extend(attributes);
+                }
+            }
+            _ => return Err(self.error("expected statement")),
+        }
+        Ok(())
+    }
+
+    fn parse_edge_stmt(
+        &mut self,
+        first: Vec<NodeIndex>,
+        scope: &mut Scope,
+    ) -> Result<(), ParseError> {
+        let mut operands = vec![first];
+        while self.peek() == Some(&Token::EdgeOp) {
+            self.pos += 1;
+            let operand = match self.peek() {
+                Some(Token::LBrace) => self.parse_subgraph(scope)?,
+                Some(t) if t.is_keyword("subgraph") => self.parse_subgraph(scope)?,
+                Some(Token::Id(..)) => vec![self.parse_node_id(scope)?],
+                _ => return Err(self.error("expected vertex or subgraph after edge operator")),
+            };
+            operands.push(operand);
+        }
+        if operands.len() == 1 {
+            return Ok(());
+        }
+
+        let mut attributes = scope.edge.clone();
+        if self.peek() == Some(&Token::LBracket) {
+            attributes.extend(self.parse_attr_list()?);
+        }
+        let weight = match get_attribute(&attributes, "weight") {
+            None => 1,
+            Some(w) => match w.parse::<f64>() {
+                Ok(w) if w >= 0. => w.round() as i32,
+                _ => {
+                    self.warn(format!("invalid edge weight '{w}', using 1 instead"));
+                    1
+                }
+            },
+        };
+
+        for pair in operands.windows(2) {
+            for tail in &pair[0] {
+                for head in &pair[1] {
+                    if tail == head {
+                        let name = self.graph[*tail].name.clone();
+                        self.warn(format!(
+                            "self loops are not supported, removed edge {name} -> {name}"
+                        ));
+                        continue;
+                    }
+                    self.graph.add_edge(
+                        *tail,
+                        *head,
+                        DotEdge {
+                            weight,
+                            attributes: attributes.clone(),
+                        },
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a subgraph and returns all vertices referenced inside it.
+    fn parse_subgraph(&mut self, scope: &Scope) -> Result<Vec<NodeIndex>, ParseError> {
+        let mut name = None;
+        if self.peek().is_some_and(|t| t.is_keyword("subgraph")) {
+            self.pos += 1;
+            if let Some(Token::Id(id, _)) = self.peek() {
+                name = Some(id.clone());
+                self.pos += 1;
+            }
+        }
+        if let Some(name) = name.as_ref().filter(|n| n.starts_with("cluster")) {
+            self.warn(format!(
+                "clusters are not supported, the vertices of {name} are laid out as regular vertices"
+            ));
+        }
+        self.expect(Token::LBrace)?;
+        self.members.push(Vec::new());
+        let mut inner_scope = scope.clone();
+        self.parse_stmt_list(&mut inner_scope, false)?;
+        self.expect(Token::RBrace)?;
+        let members = self.members.pop().unwrap();
+        if let Some(parent) = self.members.last_mut() {
+            parent.extend(members.iter().copied());
+        }
+        Ok(members)
+    }
+
+    fn parse_node_id(&mut self, scope: &Scope) -> Result<NodeIndex, ParseError> {
+        let name = self.parse_id()?;
+        // ports are not supported, edges always end at the center of a vertex
+        if self.peek() == Some(&Token::Colon) {
+            self.pos += 1;
+            let port = self.parse_id()?;
+            if self.peek() == Some(&Token::Colon) {
+                self.pos += 1;
+                self.parse_id()?;
+            }
+            self.warn_once(
+                "port",
+                format!("ports are not supported, ignoring {name}:{port}"),
+            );
+        }
+        let vertex = match self.ids.get(&name) {
+            Some(v) => *v,
+            None => {
+                let v = self.graph.add_node(DotNode {
+                    name: name.clone(),
+                    attributes: scope.node.clone(),
+                    ..Default::default()
+                });
+                self.ids.insert(name, v);
+                v
+            }
+        };
+        if let Some(members) = self.members.last_mut() {
+            members.push(vertex);
+        }
+        Ok(vertex)
+    }
+
+    fn parse_attr_list(&mut self) -> Result<Vec<(String, String)>, ParseError> {
+        let mut attributes = Vec::new();
+        while self.peek() == Some(&Token::LBracket) {
+            self.pos += 1;
+            while self.peek() != Some(&Token::RBracket) {
+                let key = self.parse_id()?;
+                self.expect(Token::Equals)?;
+                let value = self.parse_id()?;
+                attributes.push((key, value));
+                if matches!(self.peek(), Some(Token::Comma | Token::Semicolon)) {
+                    self.pos += 1;
+                }
+            }
+            self.expect(Token::RBracket)?;
+        }
+        Ok(attributes)
+    }
+
+    fn parse_id(&mut self) -> Result<String, ParseError> {
+        match self.next() {
+            Some(Token::Id(id, _)) => Ok(id),
+            _ => Err(self.error("expected identifier")),
+        }
+    }
+
+    fn set_graph_attributes(&mut self, attributes: Vec<(String, String)>, is_root: bool) {
+        if is_root {
+            self.graph_attributes.extend(attributes);
+            return;
+        }
+        for (key, value) in attributes {
+            if key == "rank" {
+                self.warn_once(
+                    "subgraph rank",
+                    format!("rank={value} is not supported, vertex ranks are determined by the ranking type"),
+                );
+            }
+        }
+    }
+
+    fn create_config(&mut self) -> Config {
+        let mut config = Config::default();
+        // vertices are as large as their shape, which is read before
+        let largest = self
+            .graph
+            .node_weights()
+            .map(|v| v.shape.width.max(v.shape.height))
+            .max()
+            .unwrap_or(0);
+        if largest > 0 {
+            config.vertex_spacing = config.vertex_spacing.max(largest + NODESEP_DEFAULT);
+        }
+
+        for (key, value) in self.graph_attributes.clone() {
+            match key.as_str() {
+                "nodesep" => match value.parse::<f64>() {
+                    // nodesep is given in inches
+                    Ok(v) if v > 0. => {
+                        config.vertex_spacing = largest + ((v * 72.).round() as usize).max(1)
+                    }
+                    _ => self.warn(format!("invalid value for nodesep: '{value}'")),
+                },
+                "splines" => match value.as_str() {
+                    "ortho" => config.edge_routing = EdgeRouting::Orthogonal,
+                    "spline" | "true" | "" => config.edge_routing = EdgeRouting::Spline,
+                    "polyline" | "line" | "false" => config.edge_routing = EdgeRouting::Polyline,
+                    _ => self.warn(format!("unsupported value for splines: '{value}'")),
+                },
+                "rankdir" if value != "TB" => self.warn_once(
+                    "rankdir",
+                    format!("rankdir={value} is not supported, layouts are always top to bottom"),
+                ),
+                "ranksep" => self.warn_once(
+                    "ranksep",
+                    "ranksep is not supported, the spacing between ranks is the vertex spacing"
+                        .to_string(),
+                ),
+                k if IGNORED_GRAPH_ATTRIBUTES.contains(&k) => {
+                    self.warn_once(k, format!("graph attribute {k} is not supported"))
+                }
+                _ => (),
+            }
+        }
+
+        let mut ignored = HashSet::new();
+        for v in self.graph.node_weights() {
+            for (key, _) in &v.attributes {
+                if IGNORED_NODE_ATTRIBUTES.contains(&key.as_str()) {
+                    ignored.insert(key.clone());
+                }
+            }
+        }
+        let mut minimum_lengths = Vec::new();
+        for e in self.graph.edge_weights() {
+            for (key, _) in &e.attributes {
+                if IGNORED_EDGE_ATTRIBUTES.contains(&key.as_str()) {
+                    ignored.insert(key.clone());
+                }
+            }
+            minimum_lengths.push(e.attribute("minlen").unwrap_or("1").to_string());
+        }
+        let mut ignored = ignored.into_iter().collect::<Vec<_>>();
+        ignored.sort();
+        for key in ignored {
+            self.warn_once(&key, format!("attribute {key} is not supported"));
+        }
+
+        let mut values = HashSet::new();
+        for minimum_length in minimum_lengths {
+            match minimum_length.parse::<u32>() {
+                Ok(0) => self.warn_once(
+                    "minlen=0",
+                    "minlen=0 is not supported, using a minimum length of 1".to_string(),
+                ),
+                Ok(v) => {
+                    values.insert(v);
+                }
+                Err(_) => self.warn(format!("invalid value for minlen: '{minimum_length}'")),
+            }
+        }
+        if let Some(max) = values.iter().max() {
+            if values.len() > 1 {
+                self.warn(format!(
+                    "minlen differs between edges, using the maximum of {max} for all edges"
+                ));
+            }
+            config.minimum_length = *max;
+        }
+        config
+    }
+
+    /// Reads the shape and the size of each vertex, see [DotNode::shape].
+    fn read_shapes(&mut self) {
+        for v in self.graph.node_indices().collect::<Vec<_>>() {
+            let node = &self.graph[v];
+            let [shape, width, height] =
+                ["shape", "width", "height"].map(|key| node.attribute(key).map(str::to_string));
+            if shape.is_none() && width.is_none() && height.is_none() {
+                continue;
+            }
+            let rounded = node
+                .attribute("style")
+                .is_some_and(|style| style.split(',').any(|s| s.trim() == "rounded"));
+
+            let shape = match shape.as_deref() {
+                None | Some("ellipse" | "oval" | "circle") => Shape::Ellipse,
+                Some("box" | "rect" | "rectangle" | "square") if rounded => Shape::RoundedRectangle,
+                Some("box" | "rect" | "rectangle" | "square") => Shape::Rectangle,
+                Some("diamond") => Shape::Diamond,
+                Some(s) => {
+                    self.warn_once(
+                        &format!("shape={s}"),
+                        format!("shape {s} is not supported, using an ellipse"),
+                    );
+                    Shape::Ellipse
+                }
+            };
+            // sizes are given in inches, the defaults are the ones of Graphviz
+            let mut size = |key: &str, value: Option<String>, default: f64| {
+                let inches = match value.as_deref().map(str::parse::<f64>) {
+                    None => default,
+                    Some(Ok(v)) if v > 0. => v,
+                    Some(_) => {
+                        self.warn(format!(
+                            "invalid value for {key}: '{}', using {default}",
+                            value.unwrap_or_default()
+                        ));
+                        default
+                    }
+                };
+                (inches * 72.).round() as usize
+            };
+            let (mut width, mut height) = (size("width", width, 0.75), size("height", height, 0.5));
+            if matches!(self.graph[v].attribute("shape"), Some("circle" | "square")) {
+                width = width.max(height);
+                height = width;
+            }
+            self.graph[v].shape = VertexShape::new(shape, width, height);
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.0)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|t| t.0.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), ParseError> {
+        match self.next() {
+            Some(t) if t == token => Ok(()),
+            _ => Err(self.error(format!("expected {token:?}"))),
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        // report the line of the token which caused the error
+        let line = self
+            .tokens
+            .get(
+                self.pos
+                    .saturating_sub(1)
+                    .min(self.tokens.len().saturating_sub(1)),
+            )
+            .map(|t| t.1)
+            .unwrap_or(1);
+        ParseError::new(line, message)
+    }
+
+    fn warn(&mut self, message: String) {
+        warn!(target: "dot", "{message}");
+        self.warnings.push(message);
+    }
+
+    fn warn_once(&mut self, key: &str, message: String) {
+        if self.warned.insert(key.to_string()) {
+            self.warn(message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::NodeIndex;
+
+    use super::parse;
+    use crate::configure::{Config, EdgeRouting};
+    use crate::{Shape, VertexShape};
+
+    #[test]
+    fn parse_simple_digraph() {
+        let dot = parse("digraph G { a -> b -> c; a -> c }").unwrap();
+        assert_eq!(dot.name.as_deref(), Some("G"));
+        assert_eq!(dot.graph.node_count(), 3);
+        assert_eq!(dot.graph.edge_count(), 3);
+        assert!(dot.warnings.is_empty());
+        assert!(dot.graph.contains_edge(0.into(), 1.into()));
+        assert!(dot.graph.contains_edge(1.into(), 2.into()));
+        assert!(dot.graph.contains_edge(0.into(), 2.into()));
+    }
+
+    #[test]
+    fn parse_comments_and_quoted_ids() {
+        let dot = parse(
+            r#"
+            /* a comment */
+            digraph {
+                // another comment
+                "first vertex" -> "second \"vertex\"";
+                # preprocessor line
+                "con" + "cat" -> <<b>html</b>>;
+            }
+            "#,
+        )
+        .unwrap();
+        let names = dot
+            .graph
+            .node_weights()
+            .map(|v| v.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["first vertex", "second \"vertex\"", "concat", "<b>html</b>"]
+        );
+    }
+
+    #[test]
+    fn parse_attributes_and_defaults() {
+        let dot = parse(
+            "digraph { node [shape=box]; a [label=\"A\"]; edge [color=red]; a -> b [weight=3] }",
+        )
+        .unwrap();
+        let a = &dot.graph[NodeIndex::from(0)];
+        let b = &dot.graph[NodeIndex::from(1)];
+        assert_eq!(a.attribute("shape"), Some("box"));
+        assert_eq!(a.to_string(), "A");
+        assert_eq!(b.to_string(), "b");
+        let e = dot.graph.edge_weights().next().unwrap();
+        assert_eq!(e.weight, 3);
+        assert_eq!(e.attribute("color"), Some("red"));
+    }
+
+    #[test]
+    fn parse_vertex_shapes() {
+        let dot = parse(
+            "digraph {
+                a [shape=box, style=\"filled,rounded\"]; b [shape=diamond, width=1, height=2];
+                c [shape=circle, width=0.5]; d [shape=star]; e [shape=box, width=x]; f;
+            }",
+        )
+        .unwrap();
+        let shapes = dot
+            .graph
+            .node_weights()
+            .map(|v| v.shape)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            shapes,
+            [
+                VertexShape::new(Shape::RoundedRectangle, 54, 36),
+                VertexShape::new(Shape::Diamond, 72, 144),
+                VertexShape::new(Shape::Ellipse, 36, 36),
+                VertexShape::new(Shape::Ellipse, 54, 36),
+                VertexShape::new(Shape::Rectangle, 54, 36),
+                VertexShape::default(),
+            ]
+        );
+        let warnings = dot.warnings.join("\n");
+        assert!(warnings.contains("star"));
+        assert!(warnings.contains("width: 'x'"));
+    }
+
+    #[test]
+    fn parse_subgraph_as_edge_operand() {
+        let dot = parse("digraph { a -> { b c } -> d }").unwrap();
+        assert_eq!(dot.graph.node_count(), 4);
+        assert_eq!(dot.graph.edge_count(), 4);
+        assert!(dot.graph.contains_edge(0.into(), 1.into()));
+        assert!(dot.graph.contains_edge(0.into(), 2.into()));
+        assert!(dot.graph.contains_edge(1.into(), 3.into()));
+        assert!(dot.graph.contains_edge(2.into(), 3.into()));
+    }
+
+    #[test]
+    fn parse_maps_config() {
+        let dot = parse("digraph { nodesep=0.5; splines=ortho; edge [minlen=2]; a -> b; b -> c }")
+            .unwrap();
+        assert_eq!(dot.config.vertex_spacing, 36);
+        assert_eq!(dot.config.minimum_length, 2);
+        assert_eq!(dot.config.edge_routing, EdgeRouting::Orthogonal);
+        assert!(dot.warnings.is_empty());
+    }
+
+    #[test]
+    fn parse_derives_vertex_spacing_from_shapes() {
+        // vertices without a shape keep the default spacing
+        let dot = parse("digraph { a -> b }").unwrap();
+        assert_eq!(dot.config.vertex_spacing, Config::default().vertex_spacing);
+
+        // the default size of Graphviz is 0.75 by 0.5 inches
+        let dot = parse("digraph { a [shape=box]; b [height=1]; a -> b; a -> c }").unwrap();
+        assert_eq!(dot.config.vertex_spacing, 72 + 18);
+        let drawings = dot.builder().build();
+        let xs = drawings[0]
+            .0
+            .iter()
+            .filter(|(_, (_, y))| *y != 0)
+            .map(|(_, (x, _))| *x)
+            .collect::<Vec<_>>();
+        assert!((xs[0] - xs[1]).abs() >= 54 + 18);
+
+        // nodesep is the space between the borders of the vertices
+        let dot = parse("digraph { nodesep=0.5; a [shape=box] }").unwrap();
+        assert_eq!(dot.config.vertex_spacing, 54 + 36);
+    }
+
+    #[test]
+    fn parse_warns_about_ignored_attributes() {
+        let dot = parse(
+            "digraph {
+                rankdir=LR; ranksep=1;
+                subgraph cluster_0 { rank=same; a; b }
+                a [width=2, height=1, fixedsize=true];
+                a -> b [minlen=2]; b -> c;
+                c -> c;
+            }",
+        )
+        .unwrap();
+        let warnings = dot.warnings.join("\n");
+        for expected in [
+            "rankdir",
+            "ranksep",
+            "rank=same",
+            "cluster_0",
+            "fixedsize",
+            "minlen",
+            "self loops",
+        ] {
+            assert!(
+                warnings.contains(expected),
+                "missing warning for {expected}"
+            );
+        }
+        assert!(!warnings.contains("width"));
+        // the maximum of the differing minimum lengths is used
+        assert_eq!(dot.config.minimum_length, 2);
+        assert_eq!(dot.graph.edge_count(), 2);
+    }
+
+    #[test]
+    fn parse_minimum_length_and_build() {
+        let dot = parse("digraph { a -> b [minlen=2]; b -> c; a -> c }").unwrap();
+        assert_eq!(dot.config.minimum_length, 2);
+        for dummy_vertices in [true, false] {
+            let drawings = dot
+                .builder()
+                .dummy_vertices(dummy_vertices)
+                .build_with_edges();
+            let coords = |v: usize| {
+                let vertices = &drawings[0].vertices;
+                vertices.iter().find(|(id, _)| id.index() == v).unwrap().1
+            };
+            let (a, b, c) = (coords(0), coords(1), coords(2));
+            // each edge spans at least two layers
+            assert_eq!(a.1 - b.1, 2 * dot.config.vertex_spacing as isize);
+            assert_eq!(b.1 - c.1, 2 * dot.config.vertex_spacing as isize);
+            assert_eq!(drawings[0].edges.len(), 3);
+        }
+    }
+
+    #[test]
+    fn parse_undirected_graph() {
+        let dot = parse("strict graph { a -- b -- c }").unwrap();
+        assert_eq!(dot.graph.edge_count(), 2);
+        assert!(dot.graph.contains_edge(0.into(), 1.into()));
+    }
+
+    #[test]
+    fn parse_invalid_input() {
+        assert!(parse("digraph { a -> }").is_err());
+        assert!(parse("graph { \"unterminated }").is_err());
+        assert_eq!(
+            parse("digraph {\n a -> b\n c [label=] }").unwrap_err().line,
+            3
+        );
+        assert!(parse("a -> b").is_err());
+    }
+
+    #[test]
+    fn parse_and_build_layout() {
+        let dot = parse("digraph { a -> b; a -> c; b -> d; c -> d }").unwrap();
+        let layouts = dot.builder().build();
+        assert_eq!(layouts.len(), 1);
+        assert_eq!(layouts[0].0.len(), 4);
+    }
+}
@@ -0,0 +1,443 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
+
+use log::info;
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
+
+use crate::{
+    configure::{Config, EdgeRouting},
+    Drawing, Shape, VertexShape,
+};
+
+use super::DotGraph;
+
+/// Length of arrowheads in points, the default of Graphviz
+static ARROW_LENGTH: f64 = 10.;
+
+/// Writes the layout of `graph` as a DOT document, in which each vertex has
+/// the attributes `pos`, `width` and `height` and each edge a `pos` spline.
+///
+/// `drawings` are the layouts returned by
+/// [CoordinatesBuilder::build_with_edges](crate::configure::CoordinatesBuilder::build_with_edges)
+/// for `graph`. Vertices are named after their index in `graph` and labeled
+/// with the [Display] implementation of their weight. Coordinates are written
+/// in points, so the document can be rendered with `neato -n`. The components
+/// are placed next to each other, separated by `config.vertex_spacing`.
+/// Vertices are drawn with a size of half the vertex spacing, the routes of
+/// the edges are clipped to it, unless they were clipped to the
+/// [VertexShape](crate::VertexShape) of the vertex already.
+///
+/// # Example
+/// ```
+/// use petgraph::stable_graph::StableDiGraph;
+/// use rust_sugiyama::configure::Config;
+///
+/// let mut graph = StableDiGraph::<&str, ()>::new();
+/// let a = graph.add_node("a");
+/// let b = graph.add_node("b");
+/// graph.add_edge(a, b, ());
+///
+/// let config = Config {
+///     vertex_spacing: 72,
+///     ..Default::default()
+/// };
+/// let drawings = rust_sugiyama::from_graph(&graph)
+///     .with_config(config)
+///     .build_with_edges();
+/// let dot = rust_sugiyama::dot::to_dot(&graph, &drawings, config);
+/// assert!(dot.contains("\"0\" [label=\"a\""));
+/// ```
+pub fn to_dot<V: Display, E>(
+    graph: &StableDiGraph<V, E>,
+    drawings: &[Drawing<NodeIndex>],
+    config: Config,
+) -> String {
+    write_dot(
+        graph,
+        drawings,
+        config,
+        None,
+        |v| v.index().to_string(),
+        |v| graph[v].to_string(),
+        |_| VertexShape::default(),
+    )
+}
+
+impl DotGraph {
+    /// Writes the graph as a DOT document with the computed positions,
+    /// see [to_dot].
+    ///
+    /// Unlike [to_dot], vertices keep the names and the shapes they have in
+    /// the original document. Their other attributes are not written.
+    pub fn to_dot(&self, drawings: &[Drawing<NodeIndex>]) -> String {
+        write_dot(
+            &self.graph,
+            drawings,
+            self.config,
+            self.name.as_deref(),
+            |v| self.graph[v].name.clone(),
+            |v| self.graph[v].to_string(),
+            |v| self.graph[v].shape,
+        )
+    }
+}
+
+fn write_dot<V, E>(
+    graph: &StableDiGraph<V, E>,
+    drawings: &[Drawing<NodeIndex>],
+    config: Config,
+    name: Option<&str>,
+    vertex_name: impl Fn(NodeIndex) -> String,
+    vertex_label: impl Fn(NodeIndex) -> String,
+    vertex_shape: impl Fn(NodeIndex) -> VertexShape,
+) -> String {
+    info!(target: "dot", "Writing DOT document for {} components", drawings.len());
+    let spacing = config.vertex_spacing as f64;
+    // vertices without a shape take up half of the space between them
+    let size = spacing / 2.;
+    let margin = graph
+        .node_indices()
+        .map(&vertex_shape)
+        .map(|s| s.width.max(s.height) as f64 / 2.)
+        .fold(size / 2., f64::max);
+    let top = drawings
+        .iter()
+        .flat_map(|d| d.vertices.iter())
+        .map(|(_, (_, y))| -y)
+        .max()
+        .unwrap_or(0) as f64;
+
+    // DOT has its origin in the bottom left corner and the y-axis points up
+    let mut positions = HashMap::new();
+    let mut routes = HashMap::<_, Vec<_>>::new();
+    let mut offset = margin;
+    for drawing in drawings {
+        let transform = |(x, y): (isize, isize)| (x as f64 + offset, top + y as f64 + margin);
+        for (v, coords) in &drawing.vertices {
+            positions.insert(*v, transform(*coords));
+        }
+        for route in &drawing.edges {
+            let points = route.points.iter().map(|p| transform(*p)).collect();
+            // routes which were clipped to the shape of a vertex already
+            // don't start or end at its center
+            let radius = |clipped| if clipped { 0. } else { size / 2. };
+            let radius = (radius(route.tail_clipped), radius(route.head_clipped));
+            routes
+                .entry((route.tail, route.head))
+                .or_default()
+                .push((points, radius));
+        }
+        let width = drawing.vertices.iter().map(|(_, (x, _))| *x).max();
+        offset += width.unwrap_or(0) as f64 + spacing;
+    }
+
+    let mut out = String::new();
+    match name {
+        Some(name) => writeln!(out, "digraph {} {{", quote(name)),
+        None => writeln!(out, "digraph {{"),
+    }
+    .unwrap();
+    writeln!(
+        out,
+        "    graph [bb=\"0,0,{},{}\"];",
+        num(offset - spacing + margin),
+        num(top + 2. * margin)
+    )
+    .unwrap();
+
+    let is_spline = config.edge_routing == EdgeRouting::Spline;
+    let inches = num(size / 72.);
+    for v in graph.node_indices() {
+        write!(
+            out,
+            "    {} [label={}",
+            quote(&vertex_name(v)),
+            quote(&vertex_label(v)),
+        )
+        .unwrap();
+        let shape = vertex_shape(v);
+        if shape.is_point() {
+            write!(out, ", width={inches}, height={inches}").unwrap();
+        } else {
+            let name = match shape.shape {
+                Shape::Rectangle | Shape::RoundedRectangle => "box",
+                Shape::Ellipse => "ellipse",
+                Shape::Diamond => "diamond",
+            };
+            write!(
+                out,
+                ", shape={name}, width={}, height={}",
+                num(shape.width as f64 / 72.),
+                num(shape.height as f64 / 72.)
+            )
+            .unwrap();
+            if shape.shape == Shape::RoundedRectangle {
+                write!(out, ", style=rounded").unwrap();
+            }
+        }
+        if let Some((x, y)) = positions.get(&v) {
+            write!(out, ", pos=\"{},{}\"", num(*x), num(*y)).unwrap();
+        }
+        writeln!(out, "];").unwrap();
+    }
+
+    for edge in graph.edge_references() {
+        write!(
+            out,
+            "    {} -> {}",
+            quote(&vertex_name(edge.source())),
+            quote(&vertex_name(edge.target()))
+        )
+        .unwrap();
+        // multi edges have the same routes, so the order doesn't matter
+        let route = routes
+            .get_mut(&(edge.source(), edge.target()))
+            .and_then(Vec::pop);
+        match route {
+            Some((points, radius)) => {
+                writeln!(out, " [pos=\"{}\"];", spline(points, radius, is_spline)).unwrap()
+            }
+            None => writeln!(out, ";").unwrap(),
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Converts the points of an [EdgeRoute](crate::EdgeRoute) into the value of a `pos` attribute.
+///
+/// The route is clipped at the border of the vertices, which extend `radius`
+/// around the tail and the head, and ends with an arrowhead pointing at the
+/// head. Straight segments are written as cubic bezier curves, so the spline
+/// consists of `3n + 1` points. If the points already are `control_points` of
+/// a spline, the first and the last curve, which lead out of the vertices,
+/// are left out instead.
+fn spline(mut points: Vec<(f64, f64)>, radius: (f64, f64), control_points: bool) -> String {
+    let (start, end) = radius;
+    if control_points {
+        if start > 0. {
+            points.drain(..3);
+        }
+        if end > 0. {
+            points.truncate(points.len() - 3);
+        }
+    } else {
+        points[0] = towards(points[0], points[1], start);
+    }
+    let n = points.len();
+    let tip = if control_points {
+        points[n - 1]
+    } else {
+        towards(points[n - 1], points[n - 2], end)
+    };
+    let length = distance(tip, points[n - 2]);
+    points[n - 1] = towards(tip, points[n - 2], ARROW_LENGTH.min(length / 2.));
+
+    let mut spline = format!(
+        "e,{},{} {},{}",
+        num(tip.0),
+        num(tip.1),
+        num(points[0].0),
+        num(points[0].1)
+    );
+    if control_points {
+        for p in &points[1..] {
+            write!(spline, " {},{}", num(p.0), num(p.1)).unwrap();
+        }
+        return spline;
+    }
+    for segment in points.windows(2) {
+        let (from, to) = (segment[0], segment[1]);
+        for t in [1. / 3., 2. / 3., 1.] {
+            let x = from.0 + (to.0 - from.0) * t;
+            let y = from.1 + (to.1 - from.1) * t;
+            write!(spline, " {},{}", num(x), num(y)).unwrap();
+        }
+    }
+    spline
+}
+
+/// Moves `from` by `length` in the direction of `to`.
+fn towards(from: (f64, f64), to: (f64, f64), length: f64) -> (f64, f64) {
+    let d = distance(from, to);
+    if d == 0. {
+        return from;
+    }
+    let length = length.min(d);
+    (
+        from.0 + (to.0 - from.0) / d * length,
+        from.1 + (to.1 - from.1) / d * length,
+    )
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+}
+
+fn num(n: f64) -> String {
+    let s = format!("{n:.2}");
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+
+    use crate::{configure::Config, dot::parse, from_graph};
+
+    use super::to_dot;
+
+    #[test]
+    fn to_dot_keeps_vertex_identities() {
+        let mut graph = StableDiGraph::<String, ()>::new();
+        let ids = (0..5)
+            .map(|i| graph.add_node(format!("v{i}")))
+            .collect::<Vec<_>>();
+        graph.add_edge(ids[0], ids[2], ());
+        graph.add_edge(ids[2], ids[3], ());
+        graph.add_edge(ids[0], ids[4], ());
+        graph.remove_node(ids[1]);
+
+        let config = Config::default();
+        let drawings = from_graph(&graph).with_config(config).build_with_edges();
+        let dot = parse(&to_dot(&graph, &drawings, config)).unwrap();
+
+        assert_eq!(dot.graph.node_count(), 4);
+        assert_eq!(dot.graph.edge_count(), 3);
+        for v in dot.graph.node_weights() {
+            let id = v.name.parse::<u32>().unwrap();
+            assert_eq!(v.to_string(), graph[NodeIndex::from(id)]);
+            assert!(v.attribute("pos").is_some());
+            assert!(v.attribute("width").is_some());
+            assert!(v.attribute("height").is_some());
+        }
+    }
+
+    #[test]
+    fn to_dot_writes_splines() {
+        let dot = parse("digraph G { a -> b -> c; a -> c; c -> a }").unwrap();
+        let drawings = dot.builder().build_with_edges();
+        let output = parse(&dot.to_dot(&drawings)).unwrap();
+
+        assert_eq!(output.name.as_deref(), Some("G"));
+        let names = output
+            .graph
+            .node_weights()
+            .map(|v| v.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b", "c"]);
+        for e in output.graph.edge_weights() {
+            let pos = e.attribute("pos").unwrap();
+            let points = pos.split(' ').collect::<Vec<_>>();
+            assert!(points[0].starts_with("e,"));
+            // arrowhead and 3n + 1 control points
+            assert_eq!((points.len() - 2) % 3, 0);
+        }
+    }
+
+    #[test]
+    fn to_dot_writes_spline_control_points() {
+        use crate::{configure::EdgeRouting, dot::DotGraph};
+
+        let dot = parse("digraph { a -> b -> c; a -> c }").unwrap();
+        let config = Config {
+            edge_routing: EdgeRouting::Spline,
+            ..dot.config
+        };
+        let drawings = dot.builder().with_config(config).build_with_edges();
+        let output = DotGraph { config, ..dot }.to_dot(&drawings);
+        let output = parse(&output).unwrap();
+        let mut lengths = output
+            .graph
+            .edge_weights()
+            .map(|e| e.attribute("pos").unwrap().split(' ').count() - 1)
+            .collect::<Vec<_>>();
+        // the curves leading out of the vertices are left out
+        let mut expected = drawings[0]
+            .edges
+            .iter()
+            .map(|route| route.points.len() - 6)
+            .collect::<Vec<_>>();
+        lengths.sort();
+        expected.sort();
+        assert_eq!(lengths, expected);
+    }
+
+    #[test]
+    fn to_dot_writes_single_curve_splines() {
+        use crate::{configure::EdgeRouting, Shape, VertexShape};
+
+        // vertices which are a point high leave a single curve between them,
+        // once the curves leading out of them are removed
+        for shape in [
+            VertexShape::new(Shape::Rectangle, 6, 1),
+            VertexShape::new(Shape::Diamond, 5, 1),
+        ] {
+            let mut graph = StableDiGraph::<&str, ()>::new();
+            let a = graph.add_node("a");
+            let b = graph.add_node("b");
+            graph.add_edge(a, b, ());
+
+            let config = Config {
+                edge_routing: EdgeRouting::Spline,
+                ..Default::default()
+            };
+            let drawings = from_graph(&graph)
+                .with_config(config)
+                .vertex_shapes(|_| shape)
+                .build_with_edges();
+            assert_eq!(drawings[0].edges[0].points.len(), 4);
+
+            let output = parse(&to_dot(&graph, &drawings, config)).unwrap();
+            let e = output.graph.edge_weights().next().unwrap();
+            // arrowhead and the 4 control points of the curve
+            assert_eq!(e.attribute("pos").unwrap().split(' ').count(), 5);
+        }
+    }
+
+    #[test]
+    fn to_dot_writes_vertex_shapes() {
+        let dot =
+            parse("digraph { nodesep=1; a [shape=box, width=0.5, height=0.25]; a -> b }").unwrap();
+        let drawings = dot.builder().build_with_edges();
+        let output = parse(&dot.to_dot(&drawings)).unwrap();
+        let a = &output.graph[NodeIndex::from(0)];
+        assert_eq!(a.attribute("shape"), Some("box"));
+        assert_eq!(a.attribute("width"), Some("0.5"));
+        assert_eq!(output.graph[NodeIndex::from(1)].attribute("shape"), None);
+
+        // the edge starts at the bottom of a and ends at the border of b,
+        // whose radius is a quarter of the vertex spacing
+        let coords = |pos: &str| {
+            let (x, y) = pos.split_once(',').unwrap();
+            (x.parse::<f64>().unwrap(), y.parse::<f64>().unwrap())
+        };
+        let (a, b) = (
+            coords(a.attribute("pos").unwrap()),
+            coords(output.graph[NodeIndex::from(1)].attribute("pos").unwrap()),
+        );
+        let e = output.graph.edge_weights().next().unwrap();
+        let pos = e.attribute("pos").unwrap().split(' ').collect::<Vec<_>>();
+        assert_eq!(coords(pos[1]), (a.0, a.1 - 9.));
+        let radius = dot.config.vertex_spacing as f64 / 4.;
+        assert_eq!(coords(pos[0].trim_start_matches("e,")), (b.0, b.1 + radius));
+    }
+
+    #[test]
+    fn to_dot_flips_y_axis() {
+        let dot = parse("digraph { a -> b }").unwrap();
+        let drawings = dot.builder().build_with_edges();
+        let output = parse(&dot.to_dot(&drawings)).unwrap();
+        let y = |i: u32| {
+            let pos = output.graph[NodeIndex::from(i)].attribute("pos").unwrap();
+            pos.split(',').nth(1).unwrap().parse::<f64>().unwrap()
+        };
+        assert!(y(0) > y(1));
+        assert!(y(1) > 0.);
+    }
+}
@@ -1,4 +1,7 @@
 use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use algorithm::{Edge, Vertex};
 use configure::CoordinatesBuilder;
@@ -8,12 +11,229 @@ use petgraph::stable_graph::StableDiGraph;
 
 mod algorithm;
 pub mod configure;
+#[cfg(feature = "dot")]
+pub mod dot;
+mod render;
 mod util;
 
-type Layout = (Vec<(usize, (isize, isize))>, usize, usize);
-type Layouts<T> = Vec<(Vec<(T, (isize, isize))>, usize, usize)>;
+type Layout<T> = (Vec<(T, (isize, isize))>, usize, usize);
+type Layouts<T> = Vec<Layout<T>>;
+type Drawings<T> = Vec<Drawing<T>>;
 type RawGraph<'a> = (&'a [u32], &'a [(u32, u32)]);
 
+/// The layout of a connected component, which in addition to the
+/// coordinates of the vertices contains the routes of the edges.
+///
+/// `width` and `height` are the same as in the layout returned by `build()`,
+/// i.e. the maximum number of vertices in a layer and the number of layers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Drawing<T> {
+    pub vertices: Vec<(T, (isize, isize))>,
+    pub edges: Vec<EdgeRoute<T>>,
+    pub width: usize,
+    pub height: usize,
+    /// The number of crossings is provably minimal. This is the case if there
+    /// are no crossings or the exact crossing minimization finished, see
+    /// [configure::Config]
+    pub crossings_optimal: bool,
+    /// Measures of the quality of the layout, see [Metrics]
+    pub metrics: Metrics,
+}
+
+impl<T> Drawing<T> {
+    fn map_ids<U>(self, f: impl Fn(T) -> U) -> Drawing<U> {
+        Drawing {
+            vertices: self
+                .vertices
+                .into_iter()
+                .map(|(id, coords)| (f(id), coords))
+                .collect(),
+            edges: self
+                .edges
+                .into_iter()
+                .map(|e| EdgeRoute {
+                    tail: f(e.tail),
+                    head: f(e.head),
+                    points: e.points,
+                    reversed: e.reversed,
+                    tail_clipped: e.tail_clipped,
+                    head_clipped: e.head_clipped,
+                })
+                .collect(),
+            width: self.width,
+            height: self.height,
+            crossings_optimal: self.crossings_optimal,
+            metrics: self.metrics,
+        }
+    }
+
+    fn into_layout(self) -> Layout<T> {
+        (self.vertices, self.width, self.height)
+    }
+}
+
+/// Measures of the quality of a [Drawing], which allow to compare the
+/// results of different configurations.
+///
+/// Lengths in ranks are measured after cycle removal and ranking, i.e. an
+/// edge of length `n` passes through `n - 1` dummy vertices. `width` and
+/// `height` are the size of the bounding box of the vertices and the edge
+/// routes, in which each vertex takes up the vertex spacing in both
+/// directions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// The number of crossings, where an edge with a weight counts as that
+    /// many parallel edges
+    pub crossings: usize,
+    /// The sum of the lengths of all edges in ranks
+    pub total_edge_length: usize,
+    /// The length of the longest edge in ranks
+    pub max_edge_length: usize,
+    pub dummy_vertices: usize,
+    /// The number of edges which were reversed to remove cycles
+    pub reversed_edges: usize,
+    /// The number of points at which the edge routes change their direction.
+    /// Splines are counted like the polylines they follow.
+    pub bends: usize,
+    pub width: usize,
+    pub height: usize,
+    /// The maximum number of vertices in a layer, including dummy vertices
+    pub max_layer_width: usize,
+}
+
+impl Metrics {
+    /// Returns the area of the bounding box.
+    pub fn area(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Returns the ratio of the width to the height of the bounding box.
+    pub fn aspect_ratio(&self) -> f64 {
+        self.width as f64 / self.height as f64
+    }
+}
+
+/// The route of an edge in a [Drawing].
+///
+/// `points` starts at the coordinates of `tail` and ends at the coordinates
+/// of `head`. If dummy vertices are included in the layout, the edge
+/// additionally passes through the coordinates of each dummy vertex.
+///
+/// With [configure::EdgeRouting::Spline], `points` are the control points of
+/// cubic bezier curves: the start point, followed by two control points and
+/// the end point of each curve, i.e. `3n + 1` points for `n` curves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EdgeRoute<T> {
+    pub tail: T,
+    pub head: T,
+    pub points: Vec<(isize, isize)>,
+    /// The edge was reversed in order to remove cycles and points upwards
+    pub reversed: bool,
+    /// `points` were clipped to the [VertexShape] of `tail`, so they start at
+    /// its outline instead of its center
+    pub tail_clipped: bool,
+    /// `points` were clipped to the [VertexShape] of `head`, so they end at
+    /// its outline instead of its center
+    pub head_clipped: bool,
+}
+
+/// The outline of a vertex, see [VertexShape].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Shape {
+    #[default]
+    Rectangle,
+    /// A rectangle whose corners are rounded with a radius of a quarter of
+    /// its smaller side
+    RoundedRectangle,
+    Ellipse,
+    /// A rhombus whose corners are the centers of the sides of the bounding
+    /// box
+    Diamond,
+}
+
+/// The shape and size of a vertex, which the routes of its edges are clipped
+/// to, so they start and end at the outline of the vertex instead of its
+/// center.
+///
+/// The size doesn't influence the coordinates of the vertices, so it should be
+/// smaller than the vertex spacing. A vertex without width or height, which is
+/// the default, is a point.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VertexShape {
+    pub shape: Shape,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl VertexShape {
+    pub fn new(shape: Shape, width: usize, height: usize) -> Self {
+        Self {
+            shape,
+            width,
+            height,
+        }
+    }
+
+    /// Returns whether the vertex has no size, i.e. edges aren't clipped.
+    pub fn is_point(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+}
+
+/// A phase of the layout algorithm, see [Progress].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Phase {
+    CycleRemoval,
+    Ranking,
+    CrossingReduction,
+    /// Calculation of the coordinates and the routes of the edges
+    Coordinates,
+}
+
+/// Reported to the progress callback of a build, see
+/// [configure::CoordinatesBuilder::progress].
+///
+/// Each phase is reported with iteration 0 when it starts, for each connected
+/// component. Afterwards, ranking reports the iterations of the network
+/// simplex and crossing reduction reports its sweeps, starting at 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    pub phase: Phase,
+    pub iteration: usize,
+}
+
+/// Cancels a build, which checks the token between the phases and in its
+/// long running loops. Clones of the token share their state, so a build can
+/// be cancelled from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Error returned by a build which was cancelled via its [CancellationToken].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("the layout was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
 /// Creates a graph layout from edges, which are given as a `&[(u32, u32)]`.
 ///
 /// It returns a [CoordinatesBuilder] which can be used to configure the
@@ -80,11 +300,7 @@ mod benchmark {
 
     #[test]
     fn r_100() {
-        let edges = graph_generator::RandomLayout::new(100)
-            .build_edges()
-            .into_iter()
-            .map(|(r, l)| (r as u32, l as u32))
-            .collect::<Vec<(u32, u32)>>();
+        let edges = graph_generator::RandomLayout::new(100).build_edges();
         let start = std::time::Instant::now();
         let _ = from_edges(&edges).build();
         println!("Random 100 edges: {}ms", start.elapsed().as_millis());
@@ -92,11 +308,7 @@ mod benchmark {
 
     #[test]
     fn r_1000() {
-        let edges = graph_generator::RandomLayout::new(1000)
-            .build_edges()
-            .into_iter()
-            .map(|(r, l)| (r as u32, l as u32))
-            .collect::<Vec<(u32, u32)>>();
+        let edges = graph_generator::RandomLayout::new(1000).build_edges();
         let start = std::time::Instant::now();
         let _ = from_edges(&edges).build();
         println!("Random 1000 edges: {}ms", start.elapsed().as_millis());
@@ -174,7 +386,7 @@ mod benchmark {
 #[cfg(test)]
 mod check_visuals {
 
-    use crate::from_vertices_and_edges;
+    use crate::{from_vertices_and_edges, Shape, VertexShape};
 
     use super::from_edges;
 
@@ -237,7 +449,7 @@ mod check_visuals {
             (8, 9),
         ];
         let (layout, width, height) = &mut from_edges(&edges).build()[0];
-        layout.sort_by(|a, b| a.0.cmp(&b.0));
+        layout.sort_by_key(|a| a.0);
 
         assert_eq!(*width, 4);
         assert_eq!(*height, 6);
@@ -339,6 +551,308 @@ mod check_visuals {
         println!("{layout:?}");
     }
 
+    #[test]
+    fn edge_routes_follow_edges() {
+        let edges = [(0, 1), (1, 2), (2, 0), (0, 3), (3, 4), (4, 2)];
+        let drawings = from_edges(&edges).build_with_edges();
+        let drawing = &drawings[0];
+        assert_eq!(drawing.edges.len(), edges.len());
+        for route in &drawing.edges {
+            let coords = |v| drawing.vertices.iter().find(|(id, _)| *id == v).unwrap().1;
+            assert!(edges.contains(&(route.tail as u32, route.head as u32)));
+            assert_eq!(route.points[0], coords(route.tail));
+            assert_eq!(*route.points.last().unwrap(), coords(route.head));
+        }
+        assert_eq!(drawing.edges.iter().filter(|e| e.reversed).count(), 1);
+    }
+
+    #[test]
+    fn orthogonal_edge_routes() {
+        use crate::configure::EdgeRouting;
+
+        let edges = [(0, 1), (0, 2), (0, 3), (1, 4), (2, 4), (3, 4), (0, 4)];
+        let drawings = from_edges(&edges)
+            .edge_routing(EdgeRouting::Orthogonal)
+            .build_with_edges();
+        let drawing = &drawings[0];
+        assert_eq!(drawing.edges.len(), edges.len());
+        for route in &drawing.edges {
+            let coords = |v| drawing.vertices.iter().find(|(id, _)| *id == v).unwrap().1;
+            assert_eq!(route.points[0], coords(route.tail));
+            assert_eq!(*route.points.last().unwrap(), coords(route.head));
+            for w in route.points.windows(2) {
+                assert!(w[0].0 == w[1].0 || w[0].1 == w[1].1);
+            }
+        }
+        // horizontal segments on the same height don't overlap, but may touch
+        // where they enter or leave the same vertex
+        let mut horizontal = drawing
+            .edges
+            .iter()
+            .flat_map(|e| e.points.windows(2).filter(|w| w[0].1 == w[1].1))
+            .map(|w| (w[0].1, w[0].0.min(w[1].0), w[0].0.max(w[1].0)))
+            .collect::<Vec<_>>();
+        horizontal.sort();
+        for w in horizontal.windows(2) {
+            assert!(w[0].0 != w[1].0 || w[0].2 <= w[1].1);
+        }
+    }
+
+    #[test]
+    fn spline_edge_routes_pass_through_polyline() {
+        use crate::configure::EdgeRouting;
+
+        let edges = [(0, 1), (1, 2), (2, 0), (0, 3), (3, 4), (4, 2)];
+        let polylines = from_edges(&edges).build_with_edges();
+        let splines = from_edges(&edges)
+            .edge_routing(EdgeRouting::Spline)
+            .build_with_edges();
+        assert_eq!(polylines[0].vertices, splines[0].vertices);
+        for (polyline, spline) in polylines[0].edges.iter().zip(&splines[0].edges) {
+            assert_eq!((polyline.tail, polyline.head), (spline.tail, spline.head));
+            let n = polyline.points.len();
+            assert_eq!(spline.points.len(), 3 * (n + 1) + 1);
+            assert_eq!(spline.points[0], polyline.points[0]);
+            assert_eq!(spline.points.last(), polyline.points.last());
+            // the curves end at the dummy vertices
+            for i in 1..n - 1 {
+                assert_eq!(spline.points[3 * i + 3], polyline.points[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn edge_routes_are_clipped_to_vertex_shapes() {
+        use crate::configure::EdgeRouting;
+
+        let edges = [(0, 1), (0, 2), (0, 3), (1, 3), (2, 3), (3, 0)];
+        for routing in [
+            EdgeRouting::Polyline,
+            EdgeRouting::Orthogonal,
+            EdgeRouting::Spline,
+        ] {
+            let drawings = from_edges(&edges)
+                .vertex_spacing(20)
+                .edge_routing(routing)
+                .vertex_shapes(|v| match v {
+                    0 => VertexShape::default(),
+                    _ => VertexShape::new(Shape::Rectangle, 6, 4),
+                })
+                .build_with_edges();
+            let drawing = &drawings[0];
+            let coords = |v| drawing.vertices.iter().find(|(id, _)| *id == v).unwrap().1;
+            let on_outline = |v, (x, y): (isize, isize)| {
+                let (cx, cy) = coords(v);
+                let (dx, dy) = ((x - cx).abs(), (y - cy).abs());
+                match v {
+                    0 => (dx, dy) == (0, 0),
+                    _ => dx <= 3 && dy <= 2 && (dx == 3 || dy == 2),
+                }
+            };
+            for route in &drawing.edges {
+                assert!(on_outline(route.tail, route.points[0]), "{routing:?}");
+                assert!(
+                    on_outline(route.head, *route.points.last().unwrap()),
+                    "{routing:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_coordinate_combination_keeps_vertex_spacing() {
+        use crate::configure::{
+            CoordinateCombination, CrossingMinimization, HDir, RankingType, VDir,
+        };
+
+        let edges = [
+            (7, 2),
+            (3, 7),
+            (0, 6),
+            (9, 12),
+            (0, 2),
+            (6, 8),
+            (9, 12),
+            (12, 4),
+            (9, 10),
+            (6, 4),
+            (7, 12),
+            (0, 3),
+            (1, 7),
+            (3, 5),
+            (4, 2),
+            (6, 5),
+            (0, 12),
+            (13, 2),
+        ];
+        let mut combinations = vec![
+            CoordinateCombination::Average,
+            CoordinateCombination::AverageMedian,
+            CoordinateCombination::MinimumWidth,
+        ];
+        for v_dir in [VDir::Up, VDir::Down] {
+            for h_dir in [HDir::Left, HDir::Right] {
+                combinations.push(CoordinateCombination::Direction(v_dir, h_dir));
+            }
+        }
+        for combination in combinations {
+            let drawings = from_edges(&edges)
+                .dummy_vertices(false)
+                .layering_type(RankingType::Original)
+                .crossing_minimization(CrossingMinimization::Median)
+                .transpose(false)
+                .coordinate_combination(combination)
+                .build();
+            for (vertices, _, _) in drawings {
+                for (id, (x, y)) in &vertices {
+                    for (other, (other_x, other_y)) in &vertices {
+                        if id != other && y == other_y {
+                            assert!((x - other_x).abs() >= 10, "{combination:?}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn straightening_reduces_bends_and_width() {
+        let edges = [(1, 5), (1, 3), (3, 5), (5, 6), (2, 6)];
+        let plain = &from_edges(&edges).build_with_edges()[0].metrics;
+        let straightened = &from_edges(&edges).straighten_passes(10).build_with_edges()[0].metrics;
+        assert!(straightened.bends < plain.bends);
+        assert!(straightened.width < plain.width);
+    }
+
+    #[test]
+    fn drawings_report_metrics() {
+        let edges = [(0, 1), (0, 2), (1, 3), (2, 3), (0, 3), (3, 0)];
+        let drawings = from_edges(&edges).vertex_spacing(10).build_with_edges();
+        let metrics = drawings[0].metrics;
+        assert_eq!(metrics.crossings, 0);
+        assert_eq!(metrics.total_edge_length, 8);
+        assert_eq!(metrics.max_edge_length, 2);
+        assert_eq!(metrics.dummy_vertices, 2);
+        assert_eq!(metrics.reversed_edges, 1);
+        assert_eq!(metrics.max_layer_width, 4);
+        assert_eq!(metrics.height, 30);
+        assert!(metrics.width >= 40);
+        assert_eq!(metrics.area(), metrics.width * 30);
+        assert_eq!(metrics.aspect_ratio(), metrics.width as f64 / 30.);
+
+        // every pair of edges of a complete bipartite graph on two layers
+        // without a common end point crosses
+        let edges = [0, 1, 2]
+            .into_iter()
+            .flat_map(|u| [3, 4, 5].map(|v| (u, v)))
+            .collect::<Vec<_>>();
+        let drawings = from_edges(&edges).build_with_edges();
+        let metrics = drawings[0].metrics;
+        assert_eq!(metrics.crossings, 9);
+        assert_eq!(metrics.total_edge_length, 9);
+        assert_eq!(metrics.dummy_vertices, 0);
+        assert_eq!(metrics.bends, 0);
+        assert_eq!(metrics.max_layer_width, 3);
+    }
+
+    #[test]
+    fn auto_configuration_is_not_worse_than_default() {
+        use crate::configure::{Config, QualityWeights};
+
+        let edges = [
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (1, 4),
+            (2, 5),
+            (3, 4),
+            (3, 6),
+            (5, 6),
+            (1, 6),
+            (2, 4),
+            (6, 7),
+            (0, 7),
+        ];
+        let weights = QualityWeights::default();
+        let score = |drawings: &[crate::Drawing<usize>], config: Config| {
+            drawings
+                .iter()
+                .map(|d| weights.score(&d.metrics, config.vertex_spacing))
+                .sum::<f64>()
+        };
+        let default = from_edges(&edges).build_with_edges();
+        let (drawings, config) = from_edges(&edges).build_auto(weights);
+        assert!(score(&drawings, config) <= score(&default, Config::default()));
+        // the returned configuration reproduces the layout
+        let rebuilt = from_edges(&edges).with_config(config).build_with_edges();
+        assert_eq!(drawings, rebuilt);
+    }
+
+    #[test]
+    fn progress_reports_phases_in_order() {
+        use std::sync::{Arc, Mutex};
+
+        use crate::Phase;
+
+        let edges = [(0, 1), (0, 2), (1, 3), (2, 3), (0, 3), (3, 0)];
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let log = reports.clone();
+        from_edges(&edges)
+            .progress(move |p| log.lock().unwrap().push(p))
+            .build_with_edges();
+        let reports = reports.lock().unwrap();
+        let starts = reports
+            .iter()
+            .filter(|p| p.iteration == 0)
+            .map(|p| p.phase)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            starts,
+            [
+                Phase::CycleRemoval,
+                Phase::Ranking,
+                Phase::CrossingReduction,
+                Phase::Coordinates
+            ]
+        );
+        assert!(reports.windows(2).all(|w| w[0].phase <= w[1].phase));
+        assert!(reports
+            .iter()
+            .any(|p| p.phase == Phase::CrossingReduction && p.iteration == 1));
+    }
+
+    #[test]
+    fn cancelled_build_returns_error() {
+        use crate::{CancellationToken, Cancelled, Phase};
+
+        let edges = [(0, 1), (0, 2), (1, 3), (2, 3), (0, 3)];
+        let token = CancellationToken::new();
+        let cancel = token.clone();
+        let result = from_edges(&edges)
+            .cancellation(token)
+            .progress(move |p| {
+                if p.phase == Phase::CrossingReduction {
+                    cancel.cancel();
+                }
+            })
+            .try_build_with_edges();
+        assert_eq!(result, Err(Cancelled));
+
+        let token = CancellationToken::new();
+        token.cancel();
+        assert!(from_edges(&edges)
+            .cancellation(token.clone())
+            .build_with_edges()
+            .is_empty());
+        assert_eq!(
+            from_edges(&edges)
+                .cancellation(token)
+                .try_build_auto(Default::default())
+                .err(),
+            Some(Cancelled)
+        );
+    }
+
     #[test]
     fn run_algo_empty_graph() {
         use super::from_edges;
@@ -0,0 +1,357 @@
+//! Renders a [Drawing] on a character grid, using box-drawing characters.
+//!
+//! Coordinates are scaled, so that vertices which are one unit apart don't
+//! overlap. The unit is the smallest distance between two layers (or between
+//! two vertices, if there is only one layer). Each vertex is drawn as a box
+//! containing its label, edges are drawn as orthogonal lines which bend in the
+//! space between two layers and end with an arrow pointing at the head.
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+
+use crate::Drawing;
+
+const UP: u8 = 1;
+const DOWN: u8 = 2;
+const LEFT: u8 = 4;
+const RIGHT: u8 = 8;
+
+/// Number of rows between the label rows of two adjacent layers
+const ROW_STEP: isize = 6;
+
+impl<T: Eq + Hash> Drawing<T> {
+    /// Renders the drawing with box-drawing characters, labeling each vertex
+    /// with the string returned by `label`.
+    ///
+    /// # Example
+    /// ```
+    /// let drawings = rust_sugiyama::from_edges(&[(0, 1)]).build_with_edges();
+    /// let rendered = drawings[0].render(|v| format!("v{v}"));
+    /// assert_eq!(rendered.lines().nth(1), Some("│v0│"));
+    /// ```
+    pub fn render(&self, label: impl Fn(&T) -> String) -> String {
+        let labels = self
+            .vertices
+            .iter()
+            .map(|(v, coords)| (label(v), *coords))
+            .collect::<Vec<_>>();
+        // routes which were clipped to the shapes of the vertices are moved
+        // back to their centers, since the vertices are drawn as boxes of
+        // their own
+        let centers = self
+            .vertices
+            .iter()
+            .map(|(v, coords)| (v, *coords))
+            .collect::<HashMap<_, _>>();
+        let routes = self
+            .edges
+            .iter()
+            .filter(|e| !e.points.is_empty())
+            .map(|e| {
+                let mut route = e.points.clone();
+                let n = route.len();
+                if e.tail_clipped {
+                    route[0] = centers[&e.tail];
+                }
+                if e.head_clipped {
+                    route[n - 1] = centers[&e.head];
+                }
+                route
+            })
+            .collect::<Vec<_>>();
+        render(&labels, &routes)
+    }
+}
+
+/// Renders the drawing with box-drawing characters, labeling each vertex with
+/// its id. Use [Drawing::render] for other labels.
+impl<T: Display + Eq + Hash> Display for Drawing<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.render(|v| v.to_string()))
+    }
+}
+
+fn render(vertices: &[(String, (isize, isize))], routes: &[Vec<(isize, isize)>]) -> String {
+    let points = vertices
+        .iter()
+        .map(|(_, p)| *p)
+        .chain(routes.iter().flatten().copied())
+        .collect::<Vec<_>>();
+    if points.is_empty() {
+        return String::new();
+    }
+
+    let label_width = vertices
+        .iter()
+        .map(|(l, _)| l.chars().count() as isize)
+        .max()
+        .unwrap_or(0);
+    let unit = smallest_gap(points.iter().map(|p| p.1))
+        .or_else(|| smallest_gap(points.iter().map(|p| p.0)))
+        .unwrap_or(1) as f64;
+    let col_step = (label_width + 3) as f64;
+    let margin = label_width / 2 + 1;
+    let min_x = points.iter().map(|p| p.0).min().unwrap();
+    let max_y = points.iter().map(|p| p.1).max().unwrap();
+    let to_cell = |(x, y): (isize, isize)| {
+        let col = margin + ((x - min_x) as f64 / unit * col_step).round() as isize;
+        let row = 1 + ((max_y - y) as f64 / unit).round() as isize * ROW_STEP;
+        (col, row)
+    };
+
+    let cells = points.iter().map(|p| to_cell(*p)).collect::<Vec<_>>();
+    let width = cells.iter().map(|c| c.0).max().unwrap() + margin + 1;
+    let height = cells.iter().map(|c| c.1).max().unwrap() + 2;
+    let mut grid = Grid::new(width as usize, height as usize);
+
+    for route in routes {
+        let route = route.iter().map(|p| to_cell(*p)).collect::<Vec<_>>();
+        for (i, segment) in route.windows(2).enumerate() {
+            let ((c1, r1), (c2, r2)) = (segment[0], segment[1]);
+            let dir = (r2 - r1).signum();
+            // bend in the space right after leaving the tail
+            let bend = if dir == 0 {
+                r1
+            } else {
+                r1 + dir * ROW_STEP / 2
+            };
+            grid.vertical(c1, r1, bend);
+            grid.horizontal(bend, c1, c2);
+            grid.vertical(c2, bend, r2);
+            if i == route.len() - 2 && dir != 0 {
+                let arrow = if dir > 0 { '▼' } else { '▲' };
+                grid.overlay(c2, r2 - 2 * dir, arrow);
+            }
+        }
+    }
+
+    for (label, coords) in vertices {
+        let (col, row) = to_cell(*coords);
+        grid.vertex(col, row, label);
+    }
+    grid.to_string()
+}
+
+/// Returns the smallest distance between two distinct values.
+fn smallest_gap(values: impl Iterator<Item = isize>) -> Option<isize> {
+    let mut values = values.collect::<Vec<_>>();
+    values.sort_unstable();
+    values.dedup();
+    values.windows(2).map(|w| w[1] - w[0]).min()
+}
+
+struct Grid {
+    lines: Vec<Vec<u8>>,
+    chars: Vec<Vec<Option<char>>>,
+}
+
+impl Grid {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            lines: vec![vec![0; width]; height],
+            chars: vec![vec![None; width]; height],
+        }
+    }
+
+    fn add(&mut self, col: isize, row: isize, bits: u8) {
+        if let Some(cell) = self
+            .lines
+            .get_mut(row as usize)
+            .and_then(|r| r.get_mut(col as usize))
+        {
+            *cell |= bits;
+        }
+    }
+
+    fn bits(&self, col: isize, row: isize) -> u8 {
+        self.lines
+            .get(row as usize)
+            .and_then(|r| r.get(col as usize))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn overlay(&mut self, col: isize, row: isize, c: char) {
+        if let Some(cell) = self
+            .chars
+            .get_mut(row as usize)
+            .and_then(|r| r.get_mut(col as usize))
+        {
+            *cell = Some(c);
+        }
+    }
+
+    fn has_overlay(&self, col: isize, row: isize) -> bool {
+        matches!(
+            self.chars
+                .get(row as usize)
+                .and_then(|r| r.get(col as usize)),
+            Some(Some(_))
+        )
+    }
+
+    fn vertical(&mut self, col: isize, from: isize, to: isize) {
+        let (top, bottom) = (from.min(to), from.max(to));
+        for row in top..=bottom {
+            let up = if row > top { UP } else { 0 };
+            let down = if row < bottom { DOWN } else { 0 };
+            self.add(col, row, up | down);
+        }
+    }
+
+    fn horizontal(&mut self, row: isize, from: isize, to: isize) {
+        let (left, right) = (from.min(to), from.max(to));
+        for col in left..=right {
+            let l = if col > left { LEFT } else { 0 };
+            let r = if col < right { RIGHT } else { 0 };
+            self.add(col, row, l | r);
+        }
+    }
+
+    fn vertex(&mut self, col: isize, row: isize, label: &str) {
+        let len = label.chars().count() as isize;
+        let left = col - (len + 2) / 2;
+        let right = left + len + 1;
+        for c in left..=right {
+            let (top, bottom) = if c == left {
+                ('┌', '└')
+            } else if c == right {
+                ('┐', '┘')
+            } else {
+                ('─', '─')
+            };
+            // connect edges leaving the vertex to its border
+            let top = match top {
+                '─' if self.bits(c, row - 2) & DOWN != 0 && !self.has_overlay(c, row - 2) => '┴',
+                t => t,
+            };
+            let bottom = match bottom {
+                '─' if self.bits(c, row + 2) & UP != 0 && !self.has_overlay(c, row + 2) => '┬',
+                b => b,
+            };
+            self.overlay(c, row - 1, top);
+            self.overlay(c, row + 1, bottom);
+        }
+        self.overlay(left, row, '│');
+        self.overlay(right, row, '│');
+        for (i, ch) in label.chars().enumerate() {
+            self.overlay(left + 1 + i as isize, row, ch);
+        }
+    }
+}
+
+impl Display for Grid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines = self
+            .lines
+            .iter()
+            .zip(&self.chars)
+            .map(|(bits, chars)| {
+                bits.iter()
+                    .zip(chars)
+                    .map(|(b, c)| c.unwrap_or_else(|| line_char(*b)))
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        let end = lines
+            .iter()
+            .rposition(|l| !l.is_empty())
+            .map_or(0, |i| i + 1);
+        f.write_str(&lines[..end].join("\n"))
+    }
+}
+
+fn line_char(bits: u8) -> char {
+    match bits {
+        0 => ' ',
+        UP | DOWN => '│',
+        LEFT | RIGHT => '─',
+        b if b == UP | DOWN => '│',
+        b if b == LEFT | RIGHT => '─',
+        b if b == DOWN | RIGHT => '┌',
+        b if b == DOWN | LEFT => '┐',
+        b if b == UP | RIGHT => '└',
+        b if b == UP | LEFT => '┘',
+        b if b == UP | DOWN | RIGHT => '├',
+        b if b == UP | DOWN | LEFT => '┤',
+        b if b == DOWN | LEFT | RIGHT => '┬',
+        b if b == UP | LEFT | RIGHT => '┴',
+        _ => '┼',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::from_edges;
+
+    #[test]
+    fn render_single_edge() {
+        let drawings = from_edges(&[(0, 1)]).build_with_edges();
+        let expected = "\
+┌─┐
+│0│
+└┬┘
+ │
+ │
+ ▼
+┌─┐
+│1│
+└─┘";
+        assert_eq!(drawings[0].to_string(), expected);
+    }
+
+    #[test]
+    fn render_bending_edges() {
+        let drawings = from_edges(&[(0, 1), (0, 2)]).build_with_edges();
+        let rendered = drawings[0].render(|v| format!("v{v}"));
+        assert_eq!(rendered.matches('▼').count(), 2);
+        assert!(rendered.contains("v0"));
+        assert!(rendered.contains("v1"));
+        assert!(rendered.contains("v2"));
+        assert!(rendered.contains('┴'));
+    }
+
+    #[test]
+    fn render_clipped_edges() {
+        use crate::{Shape, VertexShape};
+
+        let edges = [(0, 1), (0, 2)];
+        let plain = from_edges(&edges).build_with_edges();
+        // shapes larger than the vertex spacing reach the other vertices
+        for shape in [
+            VertexShape::new(Shape::Ellipse, 4, 2),
+            VertexShape::new(Shape::Rectangle, 40, 40),
+        ] {
+            let clipped = from_edges(&edges)
+                .vertex_shapes(|_| shape)
+                .build_with_edges();
+            assert_ne!(plain[0].edges, clipped[0].edges);
+            assert_eq!(plain[0].to_string(), clipped[0].to_string());
+        }
+    }
+
+    #[test]
+    fn render_reversed_edge() {
+        let drawings = from_edges(&[(0, 1), (1, 0)]).build_with_edges();
+        let rendered = drawings[0].to_string();
+        assert_eq!(rendered.matches('▼').count(), 1);
+        assert_eq!(rendered.matches('▲').count(), 1);
+    }
+
+    #[test]
+    fn render_empty_drawing() {
+        let drawings = from_edges(&[]).build_with_edges();
+        assert!(drawings.is_empty());
+        let drawing = crate::Drawing::<usize> {
+            vertices: Vec::new(),
+            edges: Vec::new(),
+            width: 0,
+            height: 0,
+            crossings_optimal: true,
+            metrics: Default::default(),
+        };
+        assert_eq!(drawing.to_string(), "");
+    }
+}
@@ -93,6 +93,44 @@ pub(super) enum IterDir {
     Backward,
 }
 
+/// Pseudo random number generator (splitmix64), so randomized steps can be
+/// reproduced from a seed.
+pub(super) struct Rng(u64);
+
+impl Rng {
+    pub(super) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(super) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Shuffles the slice in place (Fisher-Yates).
+    pub(super) fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            slice.swap(i, j);
+        }
+    }
+}
+
+#[test]
+fn rng_shuffle_is_reproducible() {
+    let mut a = (0..20).collect::<Vec<_>>();
+    let mut b = a.clone();
+    Rng::new(42).shuffle(&mut a);
+    Rng::new(42).shuffle(&mut b);
+    assert_eq!(a, b);
+    assert_ne!(a, (0..20).collect::<Vec<_>>());
+    a.sort();
+    assert_eq!(a, (0..20).collect::<Vec<_>>());
+}
+
 // TODO: implement this with binary and see if it is faster
 pub(super) fn radix_sort(mut input: Vec<usize>, key_length: usize) -> Vec<usize> {
     let mut output = vec![0; input.len()];